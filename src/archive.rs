@@ -0,0 +1,442 @@
+// Import standard library components
+use std::{
+    error::Error,                               // Error trait for boxed errors
+    fs::File,                                   // File handling
+    io::{self, BufRead, BufReader, Read, Seek}, // Streaming readers
+    path::Path,                                 // Path manipulation
+    thread::{self, JoinHandle},                 // File digest computed alongside parsing
+};
+
+// External crates
+use crossbeam_channel::{Receiver, Sender, bounded}; // Copy of the bytes read
+
+use crate::hasher::{CHUNK_SIZE, Checksums, hash_file, hash_reader};
+
+/// Compression layer recognized from the first bytes of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+}
+
+/// Archive format recognized from the first bytes of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar(Compression), // A compressed stream is assumed to contain a tar archive
+}
+
+/// File stored in an archive, with its checksums
+#[derive(Debug)]
+pub struct Member {
+    pub path: String,
+    pub checksums: Checksums,
+}
+
+/// Detects a compression format from its magic number
+pub fn detect_compression(header: &[u8]) -> Compression {
+    if header.starts_with(&[0x1f, 0x8b]) {
+        Compression::Gzip
+    } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Compression::Xz
+    } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Compression::Zstd
+    } else if header.starts_with(b"BZh") {
+        Compression::Bzip2
+    } else {
+        Compression::None
+    }
+}
+
+/// Wraps a reader with the decoder of a compression format
+pub fn decompressor<'a, R: BufRead + 'a>(
+    compression: Compression,
+    reader: R,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+        Compression::Xz => Box::new(liblzma::bufread::XzDecoder::new_multi_decoder(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+        Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+    })
+}
+
+/// Detects the archive format of a file from its first bytes
+fn detect_archive(header: &[u8]) -> Option<ArchiveKind> {
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        return Some(ArchiveKind::Zip);
+    }
+    match detect_compression(header) {
+        Compression::None => header
+            .get(257..262)
+            .is_some_and(|magic| magic == b"ustar")
+            .then_some(ArchiveKind::Tar(Compression::None)),
+        compression => Some(ArchiveKind::Tar(compression)),
+    }
+}
+
+/// Reader passing a copy of every byte read to another thread
+pub struct TeeReader<R> {
+    inner: R,
+    sender: Sender<Vec<u8>>,
+}
+
+impl<R> TeeReader<R> {
+    pub fn new(inner: R, sender: Sender<Vec<u8>>) -> Self {
+        TeeReader { inner, sender }
+    }
+
+    /// Returns the wrapped reader; dropping the sender ends the other side
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.inner.read(buf)?;
+        if bytes > 0 {
+            self.sender
+                .send(buf[..bytes].to_vec())
+                .map_err(|_| io::Error::other("hasher stopped"))?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reader over the chunks sent by a `TeeReader`; ends when the sender is dropped
+pub struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+    position: usize,
+}
+
+impl ChannelReader {
+    pub fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        ChannelReader {
+            receiver,
+            pending: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.pending.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.pending = chunk;
+                    self.position = 0;
+                }
+                Err(_) => return Ok(0), // End of file
+            }
+        }
+        let bytes = buf.len().min(self.pending.len() - self.position);
+        buf[..bytes].copy_from_slice(&self.pending[self.position..self.position + bytes]);
+        self.position += bytes;
+        Ok(bytes)
+    }
+}
+
+/// Hashes the regular files of a zip archive, listed by its central directory
+/// Local headers alone are not enough: streaming writers leave their sizes
+/// out and store them in a data descriptor after the content
+fn zip_members<R: Read + Seek>(reader: R) -> Result<Vec<Member>, Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut members = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_file() {
+            let path = file.name().to_string();
+            let checksums = hash_reader(&mut file)?;
+            members.push(Member { path, checksums });
+        }
+    }
+    Ok(members)
+}
+
+/// Hashes the regular files of a tar stream
+/// The stream must start with a ustar header, so that a compressed file that
+/// is not a tar archive is reported as such
+fn tar_members<R: Read>(mut reader: R) -> Result<Vec<Member>, Box<dyn Error>> {
+    let mut header = Vec::new();
+    (&mut reader).take(512).read_to_end(&mut header)?;
+    if header.get(257..262) != Some(b"ustar") {
+        return Err("not a tar archive".into());
+    }
+    let mut archive = tar::Archive::new(io::Cursor::new(header).chain(reader));
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let checksums = hash_reader(&mut entry)?;
+            members.push(Member { path, checksums });
+        }
+    }
+
+    // Read the rest of the stream so decoders check their trailers
+    io::copy(&mut archive.into_inner(), &mut io::sink())?;
+    Ok(members)
+}
+
+/// File whose bytes are hashed by another thread while they are read
+type TeedFile = BufReader<TeeReader<File>>;
+
+/// Opens a file so its own digests are computed from the same read as its content
+fn open_teed(path: &Path) -> io::Result<(TeedFile, JoinHandle<Result<Checksums, String>>)> {
+    let (sender, receiver) = bounded(16);
+    let file_hasher =
+        thread::spawn(move || hash_reader(ChannelReader::new(receiver)).map_err(|e| e.to_string()));
+
+    let tee = TeeReader::new(File::open(path)?, sender);
+    Ok((BufReader::with_capacity(CHUNK_SIZE, tee), file_hasher))
+}
+
+/// Reads what the parser or decoder left (padding, end of the stream)
+/// so the file digest covers the whole file, and returns it
+fn finish_teed(
+    mut reader: TeedFile,
+    file_hasher: JoinHandle<Result<Checksums, String>>,
+) -> Result<Checksums, Box<dyn Error>> {
+    io::copy(&mut reader, &mut io::sink())?;
+    drop(reader); // Ends the file digest
+    Ok(file_hasher.join().map_err(|_| "Thread archive error")??)
+}
+
+/// Hashes every file stored in a zip, tar, tar.gz, tar.xz, tar.zst or tar.bz2
+/// archive, without extracting it, along with the archive itself
+/// A tar archive is read once: its bytes are hashed while members are parsed.
+/// A zip archive is read twice: its members are found through the central
+/// directory at its end, which a single sequential pass cannot reach first,
+/// then the whole file is hashed in a second read
+pub fn hash_archive(path: &Path) -> Result<(Checksums, Vec<Member>), Box<dyn Error>> {
+    let context = |e: Box<dyn Error>| format!("Cannot read archive '{}': {}", path.display(), e);
+
+    let mut file = BufReader::with_capacity(CHUNK_SIZE, File::open(path)?);
+    match detect_archive(file.fill_buf()?) {
+        Some(ArchiveKind::Zip) => {
+            let members = zip_members(file).map_err(context)?;
+            Ok((hash_file(path)?, members))
+        }
+        Some(ArchiveKind::Tar(compression)) => {
+            drop(file);
+            let (mut reader, archive_hasher) = open_teed(path)?;
+            let members = decompressor(compression, &mut reader)
+                .map_err(|e| e.into())
+                .and_then(tar_members)
+                .map_err(|e| match compression {
+                    Compression::None => context(e),
+                    _ => context(format!("{} ({} stream)", e, compression.name()).into()),
+                })?;
+            Ok((finish_teed(reader, archive_hasher)?, members))
+        }
+        None => Err(context("not a zip or tar archive".into()).into()),
+    }
+}
+
+/// Checksums of a compressed file
+pub struct Decompressed {
+    pub compression: Compression,
+    pub content: Checksums,            // Of the decompressed data
+    pub compressed: Option<Checksums>, // Of the file itself, when requested
+}
+
+/// Hashes the decompressed content of a gzip, xz, zstd or bzip2 file
+/// Files that are not compressed are hashed as they are. With `compressed`,
+/// the digests of the file itself are computed from the same read
+pub fn hash_decompressed(path: &Path, compressed: bool) -> Result<Decompressed, Box<dyn Error>> {
+    let context = |e: Box<dyn Error>| format!("Cannot decompress '{}': {}", path.display(), e);
+
+    if compressed {
+        let (mut reader, file_hasher) = open_teed(path)?;
+        let compression = detect_compression(reader.fill_buf()?);
+        let content = hash_reader(decompressor(compression, &mut reader)?).map_err(context)?;
+        return Ok(Decompressed {
+            compression,
+            content,
+            compressed: Some(finish_teed(reader, file_hasher)?),
+        });
+    }
+
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, File::open(path)?);
+    let compression = detect_compression(reader.fill_buf()?);
+    let content = hash_reader(decompressor(compression, reader)?).map_err(context)?;
+    Ok(Decompressed {
+        compression,
+        content,
+        compressed: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// Builds a tar archive with two files
+    fn tar_bytes() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in [("a.txt", &b"abc"[..]), ("dir/b.bin", &[0u8; 5000][..])] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn check(path: &Path) {
+        let (checksums, members) = hash_archive(path).unwrap();
+        assert_eq!(checksums, hash_file(path).unwrap());
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].path, "a.txt");
+        assert_eq!(members[0].checksums.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(members[1].path, "dir/b.bin");
+        assert_eq!(members[1].checksums.size, 5000);
+    }
+
+    #[test]
+    fn test_tar_archives() {
+        let dir = tempdir().unwrap();
+        let tar = tar_bytes();
+
+        let plain = dir.path().join("a.tar");
+        std::fs::write(&plain, &tar).unwrap();
+        check(&plain);
+
+        let gz = dir.path().join("a.tar.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&gz).unwrap(), Default::default());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap();
+        check(&gz);
+
+        let xz = dir.path().join("a.tar.xz");
+        let mut encoder = liblzma::write::XzEncoder::new(File::create(&xz).unwrap(), 6);
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap();
+        check(&xz);
+
+        let zst = dir.path().join("a.tar.zst");
+        std::fs::write(&zst, zstd::encode_all(&tar[..], 3).unwrap()).unwrap();
+        check(&zst);
+    }
+
+    #[test]
+    fn test_zip_archive() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("a.txt", options).unwrap();
+        writer.write_all(b"abc").unwrap();
+        writer.start_file("dir/b.bin", options).unwrap();
+        writer.write_all(&[0u8; 5000]).unwrap();
+        writer.finish().unwrap();
+
+        check(&path);
+    }
+
+    #[test]
+    fn test_zip_data_descriptors() {
+        // Streaming writers only give sizes after each file's content
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("streamed.zip");
+        let mut writer = zip::ZipWriter::new_stream(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("a.txt", options).unwrap();
+        writer.write_all(b"abc").unwrap();
+        writer.start_file("dir/b.bin", options).unwrap();
+        writer.write_all(&[0u8; 5000]).unwrap();
+        writer.finish().unwrap();
+
+        check(&path);
+    }
+
+    #[test]
+    fn test_hash_decompressed() {
+        let dir = tempdir().unwrap();
+        let plain = dir.path().join("data.img");
+        std::fs::write(&plain, b"abc").unwrap();
+
+        let bz2 = dir.path().join("data.img.bz2");
+        let mut encoder =
+            bzip2::write::BzEncoder::new(File::create(&bz2).unwrap(), Default::default());
+        encoder.write_all(b"abc").unwrap();
+        encoder.finish().unwrap();
+
+        let result = hash_decompressed(&bz2, true).unwrap();
+        assert_eq!(result.compression, Compression::Bzip2);
+        assert_eq!(result.content, hash_file(&plain).unwrap());
+        assert_eq!(result.compressed.unwrap(), hash_file(&bz2).unwrap());
+
+        // Uncompressed files are hashed as they are
+        let result = hash_decompressed(&plain, false).unwrap();
+        assert_eq!(result.compression, Compression::None);
+        assert_eq!(result.content.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert!(result.compressed.is_none());
+    }
+
+    #[test]
+    fn test_concatenated_streams() {
+        let dir = tempdir().unwrap();
+        let plain = dir.path().join("data.txt");
+        std::fs::write(&plain, b"part1part2").unwrap();
+
+        // As written by `(xz part1; xz part2) > data.txt.xz`
+        let xz = dir.path().join("data.txt.xz");
+        let mut file = File::create(&xz).unwrap();
+        for part in [&b"part1"[..], b"part2"] {
+            let mut encoder = liblzma::write::XzEncoder::new(&mut file, 6);
+            encoder.write_all(part).unwrap();
+            encoder.finish().unwrap();
+        }
+        drop(file);
+
+        let result = hash_decompressed(&xz, false).unwrap();
+        assert_eq!(result.compression, Compression::Xz);
+        assert_eq!(result.content, hash_file(&plain).unwrap());
+    }
+
+    #[test]
+    fn test_not_an_archive() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.txt");
+        std::fs::write(&path, b"just text").unwrap();
+        assert!(hash_archive(&path).is_err());
+
+        let gz = dir.path().join("plain.txt.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&gz).unwrap(), Default::default());
+        encoder.write_all(b"just text").unwrap();
+        encoder.finish().unwrap();
+        let error = hash_archive(&gz).unwrap_err().to_string();
+        assert!(
+            error.contains("not a tar archive (gzip stream)"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// Import standard library components
+use std::{
+    fs::{self, File, OpenOptions}, // File handling
+    io::{self, Read, Write},       // Reading and writing contents
+    path::{Path, PathBuf},         // Path manipulation
+};
+
+use crate::walk::file_id;
+
+/// Temporary file a new content is written to before replacing `path`
+pub fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    PathBuf::from(temporary)
+}
+
+/// Replaces a file with a new content through a temporary file renamed over
+/// it, so that an interruption or a crash leaves either the old or the new
+/// content, never a truncated file
+pub fn replace(path: &Path, content: &[u8]) -> io::Result<()> {
+    let temporary = temporary_path(path);
+    let mut file = File::create(&temporary)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// Opens a file (created when missing) with an exclusive lock, and reads it
+/// The lock serializes read-modify-replace cycles of concurrent runs; it is
+/// released when the returned file is closed. A run waiting for the lock may
+/// get a file another run has replaced meanwhile: it then opens it again
+pub fn lock(path: &Path) -> io::Result<(File, String)> {
+    loop {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(false);
+        #[cfg(windows)]
+        {
+            // Allow the file to be renamed over while it is open
+            use std::os::windows::fs::OpenOptionsExt;
+            options.share_mode(0x7); // FILE_SHARE_READ | WRITE | DELETE
+        }
+        let mut file = options.open(path)?;
+        file.lock()?;
+
+        let current = fs::metadata(path)
+            .ok()
+            .and_then(|metadata| file_id(&metadata));
+        if current.is_some() && current != file_id(&file.metadata()?) {
+            continue; // Replaced while waiting for the lock
+        }
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        return Ok((file, content));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lock_and_replace() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("SUMS");
+
+        let (file, content) = lock(&path).unwrap();
+        assert_eq!(content, "");
+        replace(&path, b"new content\n").unwrap();
+        drop(file);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content\n");
+        assert!(!temporary_path(&path).exists());
+        assert_eq!(lock(&path).unwrap().1, "new content\n");
+    }
+}
@@ -0,0 +1,40 @@
+// Import standard library components
+use std::{
+    error::Error,     // Error trait for boxed errors
+    io::{self, Read}, // Generated input
+    time::Instant,    // Time measurement
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::hasher::hash_reader;
+use crate::{format_size, parse_size};
+
+/// Options of the `bench` subcommand
+#[derive(Args, Debug)]
+pub struct BenchOptions {
+    /// Bytes to hash (suffixes: K, M, G, T, KB, MB, ...)
+    #[arg(long, value_name = "SIZE", default_value = "1G", value_parser = parse_size)]
+    pub size: u64,
+}
+
+/// Runs the `bench` subcommand: hashes generated data with every algorithm,
+/// without touching the disk, and prints the throughput of the pipeline
+pub fn run(options: &BenchOptions) -> Result<(), Box<dyn Error>> {
+    let start_time = Instant::now();
+    let checksums = hash_reader(io::repeat(0xA5).take(options.size))?;
+    let elapsed = start_time.elapsed();
+
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 {
+        checksums.size as f64 / seconds / 1_048_576.0
+    } else {
+        0.0
+    };
+    println!("Hashed: {}", format_size(checksums.size));
+    println!("Algorithms: CRC32, MD5, SHA1, SHA256, SHA512 (in parallel)");
+    println!("Time:  {:.2?}", elapsed);
+    println!("Rate:  {:.1} MB/s", rate);
+    Ok(())
+}
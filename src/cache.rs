@@ -0,0 +1,396 @@
+// Import standard library components
+use std::{
+    collections::{HashMap, HashSet}, // Cache entries by file identity
+    env,                             // Cache location
+    error::Error,                    // Error trait for boxed errors
+    fs,                              // File handling
+    io,                              // Cache file I/O
+    path::{Path, PathBuf},           // Path manipulation
+    time::{Duration, SystemTime},    // Modification times
+};
+
+// External crates
+use clap::{Args, Subcommand}; // Command-line arguments
+
+use crate::atomic;
+use crate::hasher::{Checksums, hash_file};
+
+/// First line of the cache file, bumped when the layout changes
+const CACHE_HEADER: &str = "slashsum-cache 1";
+
+/// Files modified less than this long before hashing are not cached, because a
+/// later write within the same timestamp tick would go unnoticed
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Options of the `cache` subcommand
+#[derive(Args, Debug)]
+pub struct CacheOptions {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+/// Operations on the cache file
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Remove entries of files that no longer exist or have changed
+    Prune,
+}
+
+/// Identity and state of a file: any change invalidates its cached digests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileKey {
+    dev: u64,
+    ino: u64,
+    size: u64,
+    mtime: i128, // Nanoseconds since the Unix epoch
+    ctime: i128,
+}
+
+impl FileKey {
+    /// Reads the key of a file; None on platforms without inode numbers
+    fn of(metadata: &fs::Metadata) -> Option<FileKey> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Some(FileKey {
+                dev: metadata.dev(),
+                ino: metadata.ino(),
+                size: metadata.size(),
+                mtime: metadata.mtime() as i128 * 1_000_000_000 + metadata.mtime_nsec() as i128,
+                ctime: metadata.ctime() as i128 * 1_000_000_000 + metadata.ctime_nsec() as i128,
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            None
+        }
+    }
+}
+
+/// Cached digests of one file
+#[derive(Debug, Clone)]
+struct Entry {
+    key: FileKey,
+    path: PathBuf, // Last known path, used when pruning
+    checksums: Checksums,
+}
+
+/// Persistent digest cache (--cache)
+pub struct HashCache {
+    path: PathBuf,
+    entries: HashMap<(u64, u64), Entry>,
+    updated: HashSet<(u64, u64)>, // Entries to write back on save
+    rehash: bool,                 // Ignore cached digests but refresh them
+    pub hits: usize,
+}
+
+/// Default cache file location
+/// - snap: $SNAP_USER_COMMON/cache.tsv (the real ~/.cache is not writable)
+/// - Windows: %LOCALAPPDATA%\slashsum\cache.tsv
+/// - otherwise: $XDG_CACHE_HOME/slashsum/cache.tsv or ~/.cache/slashsum/cache.tsv
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("SNAP_USER_COMMON") {
+        return Some(PathBuf::from(dir).join("cache.tsv"));
+    }
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| crate::home_dir().map(|home| home.join(".cache")))?;
+    Some(dir.join("slashsum").join("cache.tsv"))
+}
+
+/// Escapes tabs, newlines and backslashes so a path fits in one field
+pub fn escape(path: &str) -> String {
+    path.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+/// Reverses `escape`
+pub fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Formats one cache line: key fields, digests, then the path
+fn format_entry(entry: &Entry) -> String {
+    let key = &entry.key;
+    let sums = &entry.checksums;
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        key.dev,
+        key.ino,
+        key.size,
+        key.mtime,
+        key.ctime,
+        sums.crc32,
+        sums.md5,
+        sums.sha1,
+        sums.sha256,
+        sums.sha512,
+        escape(&entry.path.to_string_lossy())
+    )
+}
+
+/// Parses one cache line; malformed lines are ignored by callers
+fn parse_entry(line: &str) -> Option<Entry> {
+    let fields: Vec<&str> = line.splitn(11, '\t').collect();
+    if fields.len() != 11 {
+        return None;
+    }
+    let key = FileKey {
+        dev: fields[0].parse().ok()?,
+        ino: fields[1].parse().ok()?,
+        size: fields[2].parse().ok()?,
+        mtime: fields[3].parse().ok()?,
+        ctime: fields[4].parse().ok()?,
+    };
+    Some(Entry {
+        key,
+        path: PathBuf::from(unescape(fields[10])),
+        checksums: Checksums {
+            size: key.size,
+            crc32: fields[5].to_string(),
+            md5: fields[6].to_string(),
+            sha1: fields[7].to_string(),
+            sha256: fields[8].to_string(),
+            sha512: fields[9].to_string(),
+        },
+    })
+}
+
+/// Parses the whole cache file; an unknown header means an empty cache
+fn parse_entries(content: &str) -> HashMap<(u64, u64), Entry> {
+    let mut lines = content.lines();
+    if lines.next() != Some(CACHE_HEADER) {
+        return HashMap::new();
+    }
+    lines
+        .filter_map(parse_entry)
+        .map(|entry| ((entry.key.dev, entry.key.ino), entry))
+        .collect()
+}
+
+/// Opens the cache file for exclusive read-modify-write access
+fn lock_cache_file(path: &Path) -> io::Result<(fs::File, String)> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    atomic::lock(path)
+}
+
+/// Replaces the content of a locked cache file
+fn rewrite(path: &Path, entries: &HashMap<(u64, u64), Entry>) -> io::Result<()> {
+    let mut output = format!("{}\n", CACHE_HEADER);
+    for entry in entries.values() {
+        output.push_str(&format_entry(entry));
+    }
+    atomic::replace(path, output.as_bytes())
+}
+
+impl HashCache {
+    /// Loads a cache file (a missing file is an empty cache)
+    pub fn open(path: PathBuf, rehash: bool) -> io::Result<HashCache> {
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => parse_entries(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(HashCache {
+            path,
+            entries,
+            updated: HashSet::new(),
+            rehash,
+            hits: 0,
+        })
+    }
+
+    /// Returns the checksums of a file, from the cache when it is unchanged
+    pub fn hash(&mut self, file: &Path) -> Result<Checksums, Box<dyn Error>> {
+        let before = fs::metadata(file)?;
+        // Devices and pseudo-files (empty in their metadata, like /proc files)
+        // can change content without changing timestamps: never cache them
+        if !before.is_file() || before.len() == 0 {
+            return hash_file(file);
+        }
+        let Some(key) = FileKey::of(&before) else {
+            return hash_file(file);
+        };
+        let id = (key.dev, key.ino);
+
+        if !self.rehash
+            && let Some(entry) = self.entries.get(&id)
+            && entry.key == key
+        {
+            self.hits += 1;
+            return Ok(entry.checksums.clone());
+        }
+
+        let started = SystemTime::now();
+        let checksums = hash_file(file)?;
+
+        // Only cache files that were stable before and during hashing
+        let after = FileKey::of(&fs::metadata(file)?);
+        let settled = before
+            .modified()
+            .is_ok_and(|mtime| mtime + RACY_WINDOW <= started);
+        if after == Some(key) && settled {
+            self.entries.insert(
+                id,
+                Entry {
+                    key,
+                    path: file.canonicalize().unwrap_or_else(|_| file.to_path_buf()),
+                    checksums: checksums.clone(),
+                },
+            );
+            self.updated.insert(id);
+        }
+        Ok(checksums)
+    }
+
+    /// Writes new entries back, merged with entries saved meanwhile by other runs
+    pub fn save(&self) -> io::Result<()> {
+        if self.updated.is_empty() {
+            return Ok(());
+        }
+        let (_lock, content) = lock_cache_file(&self.path)?;
+        let mut entries = parse_entries(&content);
+        for id in &self.updated {
+            entries.insert(*id, self.entries[id].clone());
+        }
+        rewrite(&self.path, &entries)
+    }
+}
+
+/// Removes entries of files that no longer exist or have changed
+/// Returns (kept, removed) entry counts
+pub fn prune(path: &Path) -> io::Result<(usize, usize)> {
+    let (_lock, content) = lock_cache_file(path)?;
+    let mut entries = parse_entries(&content);
+    let total = entries.len();
+
+    entries.retain(|_, entry| {
+        fs::metadata(&entry.path).is_ok_and(|metadata| FileKey::of(&metadata) == Some(entry.key))
+    });
+
+    rewrite(path, &entries)?;
+    Ok((entries.len(), total - entries.len()))
+}
+
+/// Runs the `cache` subcommand (`slashsum cache prune`)
+pub fn run(options: &CacheOptions) -> Result<(), Box<dyn Error>> {
+    let path = default_path().ok_or("Cannot determine the cache directory")?;
+    match options.action {
+        CacheAction::Prune => {
+            let (kept, removed) = prune(&path)?;
+            println!("Cache: {}", path.display());
+            println!("Entries kept: {}, removed: {}", kept, removed);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+    use tempfile::tempdir;
+
+    /// Sets a file's modification time far enough in the past to be cacheable
+    fn age(path: &Path) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_escape_roundtrip() {
+        let path = "dir\\with\ttab\nand newline";
+        assert_eq!(unescape(&escape(path)), path);
+        assert!(!escape(path).contains(['\t', '\n']));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cache_hit_and_invalidation() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("cache").join("cache.tsv");
+        let file = dir.path().join("data.bin");
+        fs::write(&file, b"abc").unwrap();
+        age(&file);
+
+        // First run hashes and saves
+        let mut cache = HashCache::open(cache_path.clone(), false).unwrap();
+        let first = cache.hash(&file).unwrap();
+        assert_eq!(cache.hits, 0);
+        cache.save().unwrap();
+
+        // Second run is served from the cache
+        let mut cache = HashCache::open(cache_path.clone(), false).unwrap();
+        assert_eq!(cache.hash(&file).unwrap(), first);
+        assert_eq!(cache.hits, 1);
+
+        // --rehash ignores the cached entry
+        let mut cache = HashCache::open(cache_path.clone(), true).unwrap();
+        cache.hash(&file).unwrap();
+        assert_eq!(cache.hits, 0);
+
+        // Any change to the file invalidates it
+        fs::write(&file, b"abd").unwrap();
+        age(&file);
+        let mut cache = HashCache::open(cache_path.clone(), false).unwrap();
+        let changed = cache.hash(&file).unwrap();
+        assert_eq!(cache.hits, 0);
+        assert_ne!(changed.md5, first.md5);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recent_files_are_not_cached() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("cache.tsv");
+        let file = dir.path().join("fresh.bin");
+        fs::write(&file, b"just written").unwrap();
+
+        let mut cache = HashCache::open(cache_path.clone(), false).unwrap();
+        cache.hash(&file).unwrap();
+        cache.save().unwrap();
+        assert!(!cache_path.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_prune() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("cache.tsv");
+        let kept = dir.path().join("kept.bin");
+        let removed = dir.path().join("removed.bin");
+        for file in [&kept, &removed] {
+            fs::write(file, file.to_string_lossy().as_bytes()).unwrap();
+            age(file);
+        }
+
+        let mut cache = HashCache::open(cache_path.clone(), false).unwrap();
+        cache.hash(&kept).unwrap();
+        cache.hash(&removed).unwrap();
+        cache.save().unwrap();
+
+        fs::remove_file(&removed).unwrap();
+        assert_eq!(prune(&cache_path).unwrap(), (1, 1));
+    }
+}
@@ -0,0 +1,199 @@
+// Import standard library components
+use std::{
+    collections::HashMap,  // Entry index by path
+    error::Error,          // Error trait for boxed errors
+    fs,                    // Manifest reading
+    path::{Path, PathBuf}, // Path manipulation
+    time::Instant,         // Time measurement
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::error::SlashsumError;
+use crate::hasher::{Algorithm, hash_file};
+use crate::{manifest, parse_path};
+
+/// Options of the `check` subcommand
+#[derive(Args, Debug)]
+pub struct CheckOptions {
+    /// Manifests to verify (sums format, tagged or untagged)
+    #[arg(value_name = "MANIFEST", required = true, value_parser = parse_path)]
+    pub manifests: Vec<PathBuf>,
+    /// Directory the listed paths are relative to (default: current directory)
+    #[arg(long, value_name = "DIR", value_parser = parse_path)]
+    pub base: Option<PathBuf>,
+}
+
+/// Digests listed for one file
+type Entry = (String, Vec<(Algorithm, String)>);
+
+/// Result of checking one listed file
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Failed(Vec<Algorithm>), // Algorithms whose digest differs
+    Missing,
+    Unreadable(String),
+}
+
+impl Status {
+    /// Text printed after the path
+    fn describe(&self) -> String {
+        match self {
+            Status::Ok => "OK".to_string(),
+            Status::Failed(algorithms) => {
+                let names: Vec<&str> = algorithms.iter().map(|algo| algo.name()).collect();
+                format!("FAILED ({})", names.join(", "))
+            }
+            Status::Missing => "MISSING".to_string(),
+            Status::Unreadable(error) => format!("UNREADABLE ({})", error),
+        }
+    }
+}
+
+/// Parses a sums manifest into entries grouped by path, in listing order
+/// A file listed with several algorithms (tagged lines) is hashed once
+fn read_entries(content: &str) -> Result<Vec<Entry>, String> {
+    if content.starts_with("%%%% HASHDEEP") {
+        return Err("hashdeep files are checked with 'slashsum audit'".to_string());
+    }
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new(); // Position in entries
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (algo, path, digest) = manifest::parse_line(line)
+            .ok_or(format!("Line {}: invalid manifest line", number + 1))?;
+        match index.get(&path) {
+            Some(&position) => entries[position].1.push((algo, digest)),
+            None => {
+                index.insert(path.clone(), entries.len());
+                entries.push((path, vec![(algo, digest)]));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Hashes one file and compares it with its listed digests
+fn check_file(path: &Path, digests: &[(Algorithm, String)]) -> Status {
+    if !path.exists() {
+        return Status::Missing;
+    }
+    let checksums = match hash_file(path) {
+        Ok(checksums) => checksums,
+        Err(e) => return Status::Unreadable(e.to_string()),
+    };
+    let failed: Vec<Algorithm> = digests
+        .iter()
+        .filter(|(algo, digest)| checksums.get(*algo) != digest)
+        .map(|(algo, _)| *algo)
+        .collect();
+    if failed.is_empty() {
+        Status::Ok
+    } else {
+        Status::Failed(failed)
+    }
+}
+
+/// Runs the `check` subcommand, printing one "path: STATUS" line per file
+/// Returns true when every listed file is present and matches
+pub fn run(options: &CheckOptions) -> Result<bool, Box<dyn Error>> {
+    let start_time = Instant::now();
+    let base = options.base.as_deref().unwrap_or(Path::new(""));
+    let (mut ok, mut failed, mut missing) = (0, 0, 0);
+
+    for manifest_path in &options.manifests {
+        let content = fs::read_to_string(manifest_path).map_err(|e| {
+            SlashsumError::io(format!("Cannot read '{}'", manifest_path.display()), &e)
+        })?;
+        let entries =
+            read_entries(&content).map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+
+        for (path, digests) in entries {
+            let status = check_file(&base.join(&path), &digests);
+            println!("{}: {}", path, status.describe());
+            match status {
+                Status::Ok => ok += 1,
+                Status::Missing => missing += 1,
+                Status::Failed(_) | Status::Unreadable(_) => failed += 1,
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Checked: {}, OK: {}, failed: {}, missing: {}",
+        ok + failed + missing,
+        ok,
+        failed,
+        missing
+    );
+    println!("Time:  {:.2?}", start_time.elapsed());
+    Ok(failed == 0 && missing == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_entries() {
+        let content = "# release 1.0\n\
+                       SHA256 (a.txt) = ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\n\
+                       MD5 (a.txt) = 900150983cd24fb0d6963f7d28e17f72\n\
+                       \n\
+                       900150983cd24fb0d6963f7d28e17f72  b.txt\n";
+        let entries = read_entries(content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, "a.txt");
+        assert_eq!(entries[0].1.len(), 2);
+        assert_eq!(entries[1].1[0].0, Algorithm::Md5);
+
+        assert!(read_entries("not a manifest line\n").is_err());
+        assert!(read_entries("%%%% HASHDEEP-1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_check_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("abc.txt");
+        fs::write(&file, b"abc").unwrap();
+        let md5 = (
+            Algorithm::Md5,
+            "900150983cd24fb0d6963f7d28e17f72".to_string(),
+        );
+        let sha1 = (Algorithm::Sha1, "0".repeat(40));
+
+        assert_eq!(check_file(&file, std::slice::from_ref(&md5)), Status::Ok);
+        assert_eq!(
+            check_file(&file, &[md5, sha1]),
+            Status::Failed(vec![Algorithm::Sha1])
+        );
+        assert_eq!(check_file(&dir.path().join("gone"), &[]), Status::Missing);
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("abc.txt"), b"abc").unwrap();
+        let sums = dir.path().join("MD5SUMS");
+        fs::write(&sums, "900150983cd24fb0d6963f7d28e17f72  abc.txt\n").unwrap();
+
+        let mut options = CheckOptions {
+            manifests: vec![sums.clone()],
+            base: Some(dir.path().to_path_buf()),
+        };
+        assert!(run(&options).unwrap());
+
+        fs::write(&sums, "900150983cd24fb0d6963f7d28e17f72  missing.txt\n").unwrap();
+        assert!(!run(&options).unwrap());
+
+        options.manifests = vec![dir.path().join("none")];
+        assert!(run(&options).is_err());
+    }
+}
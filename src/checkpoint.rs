@@ -0,0 +1,305 @@
+// Import standard library components
+use std::{
+    error::Error,                          // Error trait for boxed errors
+    fs::{self, File},                      // Checkpoint and input files
+    io::{self, BufReader, Seek, SeekFrom}, // Reading from the resume offset
+    path::{Path, PathBuf},                 // Path manipulation
+};
+
+use crate::atomic;
+use crate::cache::{escape, unescape};
+use crate::error::SlashsumError;
+use crate::hasher::{Algorithm, CHUNK_SIZE, Checksums, HashState, expected_size, hash_segment};
+use crate::interrupt::{self, Interrupted};
+use crate::xattrs::format_mtime;
+
+/// First line of a checkpoint file, bumped when the layout changes
+const HEADER: &str = "slashsum-checkpoint 1";
+
+/// Bytes hashed between two checkpoints (1GiB): at most this much is hashed
+/// again after an interruption
+pub const INTERVAL: u64 = 1 << 30;
+
+/// Progress of one file: where to continue and the state of every algorithm
+pub struct Checkpoint {
+    pub file: PathBuf,     // Absolute path of the hashed file
+    pub size: Option<u64>, // Size of the file when hashing started
+    pub mtime: String,     // Modification time when hashing started
+    pub offset: u64,       // Bytes hashed so far
+    pub state: HashState,
+}
+
+impl Checkpoint {
+    /// Serializes as "key<TAB>value" lines after the header
+    fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        text.push_str(&format!("file\t{}\n", escape(&self.file.to_string_lossy())));
+        if let Some(size) = self.size {
+            text.push_str(&format!("size\t{}\n", size));
+        }
+        text.push_str(&format!("mtime\t{}\n", self.mtime));
+        text.push_str(&format!("offset\t{}\n", self.offset));
+        for (algo, value) in self.state.export() {
+            text.push_str(&format!("{}\t{}\n", algo.name(), value));
+        }
+        text
+    }
+
+    /// Parses a checkpoint written by `to_text`
+    fn from_text(text: &str) -> Result<Checkpoint, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("Not a slashsum checkpoint file".to_string());
+        }
+
+        let (mut file, mut size, mut mtime, mut offset) = (None, None, None, None);
+        let mut fields = Vec::new();
+        for line in lines {
+            let (key, value) = line
+                .split_once('\t')
+                .ok_or(format!("Invalid checkpoint line '{}'", line))?;
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid checkpoint {} '{}'", key, value))
+            };
+            match key {
+                "file" => file = Some(PathBuf::from(unescape(value))),
+                "size" => size = Some(number()?),
+                "mtime" => mtime = Some(value.to_string()),
+                "offset" => offset = Some(number()?),
+                name => {
+                    let algo = Algorithm::ALL
+                        .into_iter()
+                        .find(|algo| algo.name() == name)
+                        .ok_or(format!("Unknown checkpoint field '{}'", name))?;
+                    fields.push((algo, value.to_string()));
+                }
+            }
+        }
+
+        let missing = |key: &str| format!("Checkpoint has no {}", key);
+        Ok(Checkpoint {
+            file: file.ok_or_else(|| missing("file"))?,
+            size,
+            mtime: mtime.ok_or_else(|| missing("mtime"))?,
+            offset: offset.ok_or_else(|| missing("offset"))?,
+            state: HashState::import(&fields)?,
+        })
+    }
+}
+
+/// Writes a checkpoint through a temporary file, so that an interruption
+/// while writing leaves the previous checkpoint intact
+pub fn save(path: &Path, checkpoint: &Checkpoint) -> io::Result<()> {
+    atomic::replace(path, checkpoint.to_text().as_bytes())
+}
+
+/// Reads a checkpoint file
+pub fn load(path: &Path) -> Result<Checkpoint, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        SlashsumError::io(format!("Cannot read checkpoint '{}'", path.display()), &e)
+    })?;
+    Ok(Checkpoint::from_text(&text)?)
+}
+
+/// Hashes a file, saving progress to `checkpoint_path` every `interval` bytes
+/// With `resume`, hashing continues from the saved offset and state, provided
+/// the file is the same and unchanged; the checkpoint is removed once done
+pub fn hash_with_checkpoints(
+    path: &Path,
+    checkpoint_path: &Path,
+    resume: bool,
+    interval: u64,
+) -> Result<Checksums, Box<dyn Error>> {
+    let mut file = File::open(path)?;
+    let absolute = fs::canonicalize(path)?;
+    let size = expected_size(&file)?;
+    let mtime = format_mtime(fs::metadata(path)?.modified()?);
+
+    let (mut state, mut offset) = if resume {
+        let checkpoint = load(checkpoint_path)?;
+        if checkpoint.file != absolute {
+            return Err(format!(
+                "Checkpoint '{}' is for '{}'",
+                checkpoint_path.display(),
+                checkpoint.file.display()
+            )
+            .into());
+        }
+        if checkpoint.size != size || checkpoint.mtime != mtime {
+            return Err(format!(
+                "'{}' changed since the checkpoint was written; start again without --resume",
+                path.display()
+            )
+            .into());
+        }
+        file.seek(SeekFrom::Start(checkpoint.offset))?;
+        (checkpoint.state, checkpoint.offset)
+    } else {
+        (HashState::new(), 0)
+    };
+    let started_at = offset;
+
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, file);
+    loop {
+        let (next, bytes) =
+            match hash_segment(&mut reader, state, Some(interval), interrupt::flag()) {
+                Ok(result) => result,
+                Err(e) => {
+                    let Some(interrupted) = e.downcast_ref::<Interrupted>() else {
+                        return Err(e);
+                    };
+                    if offset > 0 {
+                        eprintln!(
+                            "Checkpoint: '{}' at offset {} (continue with --resume)",
+                            checkpoint_path.display(),
+                            offset
+                        );
+                    }
+                    // Count every byte read by this run, not only the last segment
+                    let bytes = offset - started_at + interrupted.bytes;
+                    return Err(Interrupted { bytes }.into());
+                }
+            };
+        state = next;
+        offset += bytes;
+        if bytes < interval {
+            break; // End of file
+        }
+
+        let checkpoint = Checkpoint {
+            file: absolute.clone(),
+            size,
+            mtime: mtime.clone(),
+            offset,
+            state: state.clone(),
+        };
+        save(checkpoint_path, &checkpoint)
+            .map_err(|e| format!("Cannot write checkpoint: {}", e))?;
+    }
+
+    match fs::remove_file(checkpoint_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(state.finish(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::hash_reader;
+    use tempfile::tempdir;
+
+    /// Pseudo-random bytes and offsets (xorshift), reproducible across runs
+    fn random_numbers(seed: u64) -> impl Iterator<Item = u64> {
+        let mut state = seed;
+        std::iter::repeat_with(move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+    }
+
+    fn random_data(len: usize) -> Vec<u8> {
+        random_numbers(42).take(len).map(|n| n as u8).collect()
+    }
+
+    #[test]
+    fn test_split_at_random_offsets() {
+        // Hashing a prefix, saving the state as text and restoring it before
+        // hashing the rest gives the digests of an uninterrupted run
+        let data = random_data(300_000);
+        let expected = hash_reader(&data[..]).unwrap();
+
+        for split in random_numbers(7).take(8) {
+            let split = split % data.len() as u64;
+            let mut reader = &data[..];
+            let (state, bytes) = hash_segment(
+                &mut reader,
+                HashState::new(),
+                Some(split),
+                interrupt::flag(),
+            )
+            .unwrap();
+            assert_eq!(bytes, split);
+
+            let checkpoint = Checkpoint {
+                file: PathBuf::from("/data/disk\timage.img"),
+                size: Some(data.len() as u64),
+                mtime: "1.000000000".to_string(),
+                offset: bytes,
+                state,
+            };
+            let restored = Checkpoint::from_text(&checkpoint.to_text()).unwrap();
+            assert_eq!(restored.file, checkpoint.file);
+            assert_eq!(restored.offset, split);
+
+            let (state, rest) =
+                hash_segment(&mut reader, restored.state, None, interrupt::flag()).unwrap();
+            assert_eq!(state.finish(split + rest), expected, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("disk.img");
+        let data = random_data(100_000);
+        fs::write(&input, &data).unwrap();
+        let checkpoint_path = dir.path().join("disk.ckpt");
+
+        // Checkpoints are written along the way and removed once done
+        let checksums = hash_with_checkpoints(&input, &checkpoint_path, false, 4096).unwrap();
+        assert_eq!(checksums, hash_reader(&data[..]).unwrap());
+        assert!(!checkpoint_path.exists());
+
+        // Resume from a checkpoint saved at a random offset, as an interrupted run would
+        for split in random_numbers(3).take(4) {
+            let split = split % data.len() as u64;
+            let mut reader = &data[..];
+            let (state, offset) = hash_segment(
+                &mut reader,
+                HashState::new(),
+                Some(split),
+                interrupt::flag(),
+            )
+            .unwrap();
+            let checkpoint = Checkpoint {
+                file: fs::canonicalize(&input).unwrap(),
+                size: Some(data.len() as u64),
+                mtime: format_mtime(fs::metadata(&input).unwrap().modified().unwrap()),
+                offset,
+                state,
+            };
+            save(&checkpoint_path, &checkpoint).unwrap();
+
+            let resumed = hash_with_checkpoints(&input, &checkpoint_path, true, 4096).unwrap();
+            assert_eq!(resumed, checksums);
+        }
+    }
+
+    #[test]
+    fn test_resume_changed_file() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("disk.img");
+        fs::write(&input, b"abc").unwrap();
+        let checkpoint_path = dir.path().join("disk.ckpt");
+
+        let checkpoint = Checkpoint {
+            file: fs::canonicalize(&input).unwrap(),
+            size: Some(3),
+            mtime: "1.000000000".to_string(), // Not the current mtime
+            offset: 1,
+            state: HashState::new(),
+        };
+        save(&checkpoint_path, &checkpoint).unwrap();
+        assert!(hash_with_checkpoints(&input, &checkpoint_path, true, 4096).is_err());
+
+        fs::write(&checkpoint_path, "not a checkpoint").unwrap();
+        assert!(load(&checkpoint_path).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+// Import standard library components
+use std::{
+    fs,                // Man page directory
+    io::{self, Write}, // Generated scripts and pages
+    path::PathBuf,     // Path manipulation
+};
+
+// External crates
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand}; // Command-line parsing
+use clap_complete::Shell; // Completion script generation
+
+use crate::{BUILD_VERSION, GIT_COMMIT, HashArgs, parse_path};
+use crate::{bench, cache, check, config, diff, dupes, find, hashdeep, scrub, watch};
+
+/// Usage examples and exit codes shown after the options in `slashsum --help`
+const AFTER_HELP: &str = "\
+Examples:
+  slashsum file.txt                 Calculate and display checksums
+  slashsum -s file.txt              Save results to file.txt.checksum
+  slashsum -q -a sha256 file.txt    Print only the SHA256 digest
+  slashsum file.iso --expect <HEX>  Check a published digest (exit code 1 if none matches)
+  slashsum data.bin --xattr-verify  Recheck digests stored with --xattr-store
+  slashsum image.img.xz --decompress --expect <HEX>
+                                    Check a digest published for the uncompressed image
+  slashsum disk.img --offset 1M --length 512
+                                    Hash one sector of a disk image
+  sudo slashsum /dev/sdb            Hash a whole block device
+  slashsum dist -m SHA256SUMS --base dist -a sha256
+                                    SHA256SUMS for a release directory
+  slashsum check SHA256SUMS --base dist
+                                    Verify the files listed in a manifest
+  slashsum find /srv/share --hash <HEX>
+                                    List files with this digest (exit code 1 if none)
+  slashsum diff /data /mnt/copy     Compare two trees by content (exit code 1 if they differ)
+  slashsum -- --odd-name            Hash a file whose name starts with a dash
+
+Exit codes:
+  0    Success
+  1    Mismatch: digest, check, audit, diff, find or scrub failure
+  2    Usage error (invalid option or combination of options)
+  3    A file changed while being hashed
+  4    File not found
+  5    Permission denied
+  6    Read or I/O error
+  130  Interrupted (Ctrl-C, SIGTERM)";
+
+/// License printed by `slashsum --version`
+const LICENSE: &str = r#"MIT License
+
+Copyright (c) 2025-2026 Nicolas DEOUX
+                   NDXDev@gmail.com
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE."#;
+
+/// Calculate multiple checksums simultaneously
+///
+/// Without a command, the arguments are those of `slashsum hash`.
+#[derive(Parser)]
+#[command(
+    name = "slashsum",
+    version = BUILD_VERSION,
+    author = "Nicolas DEOUX <NDXDev@gmail.com>",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    after_help = AFTER_HELP
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Print the version and commit on stderr before running
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub hash: HashArgs,
+}
+
+/// Subcommands
+#[derive(Subcommand)]
+pub enum Command {
+    /// Calculate the checksums of files (default command)
+    #[command(after_help = AFTER_HELP)]
+    Hash(Box<HashArgs>),
+    /// Verify files against a sums manifest (exit code 1 on failure)
+    Check(check::CheckOptions),
+    /// Find files matching given digests (exit code 1 if none)
+    Find(find::FindArgs),
+    /// List duplicate files, or replace them with hard links
+    Dupes(dupes::DupesArgs),
+    /// Compare two directory trees by content (exit code 1 if they differ)
+    Diff(diff::DiffOptions),
+    /// Audit files against a hashdeep known file (exit code 1 if it fails)
+    Audit(hashdeep::AuditOptions),
+    /// Detect bit rot by rehashing a tree slowly (exit code 1 on corruption)
+    Scrub(scrub::ScrubArgs),
+    /// Hash files as they change, printing NDJSON events
+    Watch(watch::WatchOptions),
+    /// Manage the digest cache
+    Cache(cache::CacheOptions),
+    /// Show the defaults read from the config file and environment
+    Config(config::ConfigOptions),
+    /// Measure hashing throughput
+    Bench(bench::BenchOptions),
+    /// Print a shell completion script
+    Completions(CompletionsOptions),
+    /// Print the man page in roff format
+    Man(ManOptions),
+}
+
+/// Options of the `completions` subcommand
+#[derive(Args, Debug)]
+pub struct CompletionsOptions {
+    /// Shell to complete for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// Options of the `man` subcommand
+#[derive(Args, Debug)]
+pub struct ManOptions {
+    /// Write one page per command into DIR (slashsum.1, slashsum-check.1, ...)
+    #[arg(long, value_name = "DIR", value_parser = parse_path)]
+    pub dir: Option<PathBuf>,
+}
+
+/// Command definition, with the license in the `--version` output
+pub fn command() -> clap::Command {
+    Cli::command().long_version(format!("{} - {}\n\n{}", BUILD_VERSION, GIT_COMMIT, LICENSE))
+}
+
+/// Prints the completion script of a shell, e.g. for
+/// /usr/share/bash-completion/completions/slashsum
+pub fn print_completions(options: &CompletionsOptions) -> io::Result<()> {
+    // Generated in memory: clap_complete panics on write errors (closed pipe)
+    let mut script = Vec::new();
+    clap_complete::generate(options.shell, &mut command(), "slashsum", &mut script);
+    io::stdout().write_all(&script)
+}
+
+/// Prints the man page, or writes the pages of every command to a directory
+/// The version section gets the plain version, not the license
+pub fn write_man(options: &ManOptions) -> io::Result<()> {
+    match &options.dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(Cli::command(), dir)
+        }
+        None => clap_mangen::Man::new(Cli::command()).render(&mut io::stdout()),
+    }
+}
+
+/// Parses the command line; usage errors, help and version requests exit
+pub fn parse() -> Cli {
+    command()
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| exit(e))
+}
+
+/// Reports an invalid combination of options like clap's own errors, then exits
+pub fn exit_usage(message: &str) -> ! {
+    exit(command().error(clap::error::ErrorKind::ArgumentConflict, message))
+}
+
+/// Prints help or version on stdout, or a "slashsum: error: ..." usage error
+/// on stderr, then exits (status 2 for errors)
+fn exit(error: clap::Error) -> ! {
+    if error.use_stderr() {
+        eprint!("slashsum: ");
+        let _ = error.print();
+        std::process::exit(error.exit_code());
+    }
+    error.exit()
+}
+
+/// Parses the arguments of one command (program name excluded), for tests
+#[cfg(test)]
+pub fn try_parse<T: clap::Args + FromArgMatches>(args: &[String]) -> Result<T, String> {
+    let command = T::augment_args(clap::Command::new("slashsum"));
+    let matches = command
+        .try_get_matches_from(std::iter::once("slashsum").chain(args.iter().map(String::as_str)))
+        .map_err(|e| e.to_string())?;
+    T::from_arg_matches(&matches).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(list: &[&str]) -> Result<Cli, clap::Error> {
+        command()
+            .try_get_matches_from(std::iter::once(&"slashsum").chain(list))
+            .and_then(|m| Cli::from_arg_matches(&m))
+    }
+
+    #[test]
+    fn test_definitions() {
+        command().debug_assert();
+    }
+
+    #[test]
+    fn test_default_command() {
+        // Files without a command are hashed, options anywhere
+        let cli = parse(&["--save", "a.txt", "-l", "b.txt"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.hash.files, vec!["a.txt", "b.txt"]);
+
+        let cli = parse(&["hash", "-sl", "a.txt"]).unwrap();
+        let Some(Command::Hash(args)) = cli.command else {
+            panic!("not the hash command");
+        };
+        assert!(args.save && args.lock);
+
+        // After "--", names are files even when they look like options or commands
+        let cli = parse(&["--", "--save", "diff"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.hash.files, vec!["--save", "diff"]);
+
+        let cli = parse(&["diff", "a", "b"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Diff(_))));
+
+        // --verbose is accepted by every command
+        assert!(parse(&["check", "SUMS", "-v"]).unwrap().verbose);
+        assert!(parse(&["-v", "a.txt"]).unwrap().verbose);
+    }
+
+    #[test]
+    fn test_man_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ManOptions {
+            dir: Some(dir.path().join("man1")),
+        };
+        write_man(&options).unwrap();
+        let page = fs::read_to_string(dir.path().join("man1/slashsum.1")).unwrap();
+        assert!(page.starts_with(".ie"), "{}", &page[..40]);
+        assert!(page.contains("\\-\\-save"));
+        assert!(dir.path().join("man1/slashsum-check.1").exists());
+        assert!(dir.path().join("man1/slashsum-cache-prune.1").exists());
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let error = parse(&["file", "--sav"]).err().unwrap().to_string();
+        assert!(error.contains("--save"), "{}", error);
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["diff", "a"]).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+// Import standard library components
+use std::{
+    env,           // Environment overrides and config directory
+    error::Error,  // Error trait for boxed errors
+    fmt,           // Display implementation
+    fs,            // Config file reading
+    io,            // Missing config file
+    path::PathBuf, // Path manipulation
+};
+
+// External crates
+use clap::{Args, Subcommand}; // Command-line arguments
+use toml::{Table, Value}; // Config file parsing
+
+use crate::Output;
+use crate::error::SlashsumError;
+use crate::hasher::parse_algorithms;
+use crate::manifest::ManifestFormat;
+
+/// Settings that can be given a default, with what they stand for
+const SETTINGS: [(&str, &str); 7] = [
+    ("algorithms", "--algo"),
+    ("format", "--format"),
+    ("output", "--quiet, --porcelain"),
+    ("save", "--save"),
+    ("cache", "--cache"),
+    ("lock", "--lock"),
+    ("retry", "--retry"),
+];
+
+/// Options of the `config` subcommand
+#[derive(Args, Debug)]
+pub struct ConfigOptions {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+/// Actions of the `config` subcommand
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective settings and where each one comes from
+    Show,
+}
+
+/// Where the value of a setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    Env(String), // Variable name
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::Env(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Defaults of the hash command, from the config file and the environment
+/// Command-line options take precedence over both
+#[derive(Debug, Default)]
+pub struct Config {
+    pub algorithms: Option<String>, // Checked list, as --algo takes it
+    pub format: Option<ManifestFormat>,
+    pub output: Option<Output>,
+    pub save: bool,
+    pub cache: bool,
+    pub lock: bool,
+    pub retry: Option<u32>,
+    pub path: Option<PathBuf>,                    // Config file location
+    sources: Vec<(&'static str, String, Source)>, // Setting, value, source
+}
+
+/// Config file location
+/// - snap: $SNAP_REAL_HOME/.config/slashsum/config.toml (snapd points
+///   XDG_CONFIG_HOME into the snap's own data)
+/// - Windows: %APPDATA%\slashsum\config.toml
+/// - otherwise: $XDG_CONFIG_HOME/slashsum/config.toml or ~/.config/slashsum/config.toml
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(home) = env::var_os("SNAP_REAL_HOME") {
+        let dir = PathBuf::from(home).join(".config");
+        return Some(dir.join("slashsum").join("config.toml"));
+    }
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| crate::home_dir().map(|home| home.join(".config")))?;
+    Some(dir.join("slashsum").join("config.toml"))
+}
+
+/// Environment variable overriding a setting
+fn env_name(setting: &str) -> String {
+    format!("SLASHSUM_{}", setting.to_ascii_uppercase())
+}
+
+/// Text of a config file value: lists of algorithms are joined with commas
+fn value_text(setting: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Boolean(flag) => Ok(flag.to_string()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(|names| names.join(","))
+            .ok_or(format!("{}: expected a list of names", setting)),
+        _ => Err(format!("{}: unsupported value", setting)),
+    }
+}
+
+/// Parses a boolean setting (true/false, yes/no, on/off, 1/0)
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("expected true or false, not '{}'", value)),
+    }
+}
+
+impl Config {
+    /// Reads the config file, if any, then the SLASHSUM_* environment variables
+    pub fn load() -> Result<Config, SlashsumError> {
+        let path = default_path();
+        let content = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(SlashsumError::io(
+                        format!("Cannot read '{}'", path.display()),
+                        &e,
+                    ));
+                }
+            },
+            None => None,
+        };
+        let mut config = Config::parse(content.as_deref(), |name| env::var(name).ok()).map_err(
+            |e| match &path {
+                Some(path) if content.is_some() => {
+                    SlashsumError::Read(format!("{}: {}", path.display(), e))
+                }
+                _ => SlashsumError::Read(e),
+            },
+        )?;
+        config.path = path;
+        Ok(config)
+    }
+
+    /// Builds the settings from a config file content and an environment
+    fn parse(
+        content: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Config, String> {
+        let table: Table = match content {
+            Some(content) => content
+                .parse()
+                .map_err(|e| format!("{}", e).trim().to_string())?,
+            None => Table::new(),
+        };
+        if let Some(key) = table
+            .keys()
+            .find(|key| !SETTINGS.iter().any(|(setting, _)| setting == key))
+        {
+            return Err(format!("unknown setting '{}'", key));
+        }
+
+        let mut config = Config::default();
+        for (setting, _) in SETTINGS {
+            let name = env_name(setting);
+            let (value, source) = match (env(&name), table.get(setting)) {
+                (Some(value), _) => (value, Source::Env(name)),
+                (None, Some(value)) => (value_text(setting, value)?, Source::File),
+                (None, None) => continue,
+            };
+            config
+                .set(setting, &value)
+                .map_err(|e| format!("{} ({}): {}", setting, source, e))?;
+            config.sources.push((setting, value, source));
+        }
+        Ok(config)
+    }
+
+    /// Checks and stores one setting
+    fn set(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "algorithms" => {
+                parse_algorithms(value)?;
+                self.algorithms = Some(value.to_string());
+            }
+            "format" => self.format = Some(ManifestFormat::from_name(value)?),
+            "output" => self.output = Some(Output::from_name(value)?),
+            "save" => self.save = parse_bool(value)?,
+            "cache" => self.cache = parse_bool(value)?,
+            "lock" => self.lock = parse_bool(value)?,
+            "retry" => {
+                let retry = value
+                    .parse()
+                    .map_err(|_| format!("expected a number, not '{}'", value))?;
+                self.retry = Some(retry);
+            }
+            _ => unreachable!("setting not in SETTINGS"),
+        }
+        Ok(())
+    }
+
+    /// Value and source of a setting, built-in defaults included
+    fn describe(&self, setting: &str) -> (String, Source) {
+        if let Some((_, value, source)) = self.sources.iter().find(|(name, ..)| *name == setting) {
+            return (value.clone(), source.clone());
+        }
+        let value = match setting {
+            "algorithms" => "all (md5,sha256 for hashdeep)",
+            "format" => "sums",
+            "output" => "normal",
+            "retry" => "0",
+            _ => "false",
+        };
+        (value.to_string(), Source::Default)
+    }
+}
+
+/// Runs the `config` subcommand
+pub fn run(options: &ConfigOptions) -> Result<(), Box<dyn Error>> {
+    match options.action {
+        ConfigAction::Show => {
+            let config = Config::load()?;
+            match &config.path {
+                Some(path) if path.exists() => println!("Config file: {}", path.display()),
+                Some(path) => println!("Config file: {} (not found)", path.display()),
+                None => println!("Config file: none (no home directory)"),
+            }
+            println!();
+            for (setting, option) in SETTINGS {
+                let (value, source) = config.describe(setting);
+                println!("{:<10} = {:<30} # {}, {}", setting, value, option, source);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let content = "algorithms = [\"sha256\", \"sha512\"]\nsave = true\nretry = 2\n";
+        let config = Config::parse(Some(content), no_env).unwrap();
+        assert_eq!(config.algorithms.as_deref(), Some("sha256,sha512"));
+        assert!(config.save && !config.cache);
+        assert_eq!(config.retry, Some(2));
+        assert_eq!(config.describe("save"), ("true".to_string(), Source::File));
+        assert_eq!(config.describe("lock").1, Source::Default);
+
+        assert!(Config::parse(Some("sav = true\n"), no_env).is_err());
+        assert!(Config::parse(Some("save = \"maybe\"\n"), no_env).is_err());
+        assert!(Config::parse(Some("format = \"xml\"\n"), no_env).is_err());
+        assert!(Config::parse(Some("output = \"loud\"\n"), no_env).is_err());
+        assert!(Config::parse(Some("algorithms = \"crc64\"\n"), no_env).is_err());
+        assert!(Config::parse(Some("save = \n"), no_env).is_err());
+    }
+
+    #[test]
+    fn test_environment_overrides() {
+        let env = |name: &str| match name {
+            "SLASHSUM_SAVE" => Some("no".to_string()),
+            "SLASHSUM_FORMAT" => Some("hashdeep".to_string()),
+            "SLASHSUM_OUTPUT" => Some("quiet".to_string()),
+            _ => None,
+        };
+        let config = Config::parse(Some("save = true\ncache = true\n"), env).unwrap();
+        assert!(!config.save && config.cache);
+        assert_eq!(config.format, Some(ManifestFormat::Hashdeep));
+        assert_eq!(config.output, Some(Output::Quiet));
+        assert_eq!(
+            config.describe("save").1,
+            Source::Env("SLASHSUM_SAVE".to_string())
+        );
+
+        let error = Config::parse(None, |_| Some("x".to_string())).unwrap_err();
+        assert!(error.contains("SLASHSUM_"), "{}", error);
+    }
+}
@@ -0,0 +1,316 @@
+// Import standard library components
+use std::{
+    collections::{BTreeMap, HashSet}, // Sorted file lists and size lookup
+    error::Error,                     // Error trait for boxed errors
+    fmt::Display,                     // Warnings about unreadable files
+    fs,                               // File system access
+    path::{Path, PathBuf},            // Path manipulation
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::hasher::hash_file;
+use crate::{manifest::manifest_path, parse_path, walk};
+
+/// Options of the `diff` subcommand
+#[derive(Args, Debug)]
+pub struct DiffOptions {
+    /// First tree
+    #[arg(value_name = "DIR_A", value_parser = parse_path)]
+    pub dir_a: PathBuf,
+    /// Second tree
+    #[arg(value_name = "DIR_B", value_parser = parse_path)]
+    pub dir_b: PathBuf,
+}
+
+/// Result of comparing two trees; paths are relative to each root
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    pub identical: Vec<String>,
+    pub modified: Vec<String>,
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    pub moved: Vec<(String, String)>, // (path in A, path in B)
+    pub unreadable: Vec<PathBuf>,     // Files and directories that could not be read
+}
+
+impl DiffReport {
+    /// True when both trees have the same files with the same content
+    pub fn is_identical(&self) -> bool {
+        self.modified.is_empty()
+            && self.only_a.is_empty()
+            && self.only_b.is_empty()
+            && self.moved.is_empty()
+            && self.unreadable.is_empty()
+    }
+
+    /// Reports a file that could not be read or hashed
+    fn skip(&mut self, file: &Path, error: impl Display) {
+        eprintln!("Warning: {}: {}", file.display(), error);
+        self.unreadable.push(file.to_path_buf());
+    }
+
+    /// SHA256 of a file, None when it cannot be read
+    fn sha256(&mut self, file: &Path) -> Option<String> {
+        match hash_file(file) {
+            Ok(checksums) => Some(checksums.sha256),
+            Err(e) => {
+                self.skip(file, e);
+                None
+            }
+        }
+    }
+}
+
+/// Lists the files of a tree by relative path, with their sizes
+/// Files and directories that cannot be read are added to the report
+fn list_tree(
+    root: &Path,
+    report: &mut DiffReport,
+) -> Result<BTreeMap<String, (PathBuf, u64)>, Box<dyn Error>> {
+    let tree = walk::collect_tree(root)?;
+    report.unreadable.extend(tree.unreadable);
+
+    let mut files = BTreeMap::new();
+    for file in tree.files {
+        let listed = manifest_path(&file, Some(root), None)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|relative| Ok((relative, fs::metadata(&file)?.len())));
+        match listed {
+            Ok((relative, size)) => {
+                files.insert(relative, (file, size));
+            }
+            Err(e) => report.skip(&file, e),
+        }
+    }
+    Ok(files)
+}
+
+/// Compares two trees by content
+/// Files at the same path are hashed only when their sizes are equal, and
+/// files present on one side only are hashed only when a file of the same
+/// size exists on the other side, to detect moves and renames
+/// Unreadable files are reported with a warning and listed as such only
+pub fn compare(dir_a: &Path, dir_b: &Path) -> Result<DiffReport, Box<dyn Error>> {
+    let mut report = DiffReport::default();
+    let tree_a = list_tree(dir_a, &mut report)?;
+    let tree_b = list_tree(dir_b, &mut report)?;
+
+    // Files present in both trees
+    for (path, (file_a, size_a)) in &tree_a {
+        let Some((file_b, size_b)) = tree_b.get(path) else {
+            continue;
+        };
+        if size_a != size_b {
+            report.modified.push(path.clone());
+            continue;
+        }
+        let (Some(digest_a), Some(digest_b)) = (report.sha256(file_a), report.sha256(file_b))
+        else {
+            continue;
+        };
+        if digest_a == digest_b {
+            report.identical.push(path.clone());
+        } else {
+            report.modified.push(path.clone());
+        }
+    }
+
+    // Files present on one side only
+    let only_a: Vec<(&String, &(PathBuf, u64))> = tree_a
+        .iter()
+        .filter(|(path, _)| !tree_b.contains_key(*path))
+        .collect();
+    let only_b: Vec<(&String, &(PathBuf, u64))> = tree_b
+        .iter()
+        .filter(|(path, _)| !tree_a.contains_key(*path))
+        .collect();
+
+    // Digests of the files in B that could be the destination of a move
+    let sizes_a: HashSet<u64> = only_a.iter().map(|(_, (_, size))| *size).collect();
+    let mut candidates_b: BTreeMap<(u64, String), Vec<&String>> = BTreeMap::new();
+    let mut unreadable_b = HashSet::new();
+    for (path, (file, size)) in &only_b {
+        if sizes_a.contains(size) {
+            match report.sha256(file) {
+                Some(digest) => candidates_b.entry((*size, digest)).or_default().push(path),
+                None => {
+                    unreadable_b.insert(*path);
+                }
+            }
+        }
+    }
+
+    // Pair each file of A with an unused file of B with the same content
+    let sizes_b: HashSet<u64> = candidates_b.keys().map(|(size, _)| *size).collect();
+    let mut moved_to = HashSet::new();
+    for (path, (file, size)) in &only_a {
+        let destination = if sizes_b.contains(size) {
+            let Some(digest) = report.sha256(file) else {
+                continue;
+            };
+            candidates_b
+                .get_mut(&(*size, digest))
+                .and_then(|paths| (!paths.is_empty()).then(|| paths.remove(0)))
+        } else {
+            None
+        };
+
+        match destination {
+            Some(destination) => {
+                moved_to.insert(destination.clone());
+                report.moved.push(((*path).clone(), destination.clone()));
+            }
+            None => report.only_a.push((*path).clone()),
+        }
+    }
+    report.only_b = only_b
+        .into_iter()
+        .map(|(path, _)| path.clone())
+        .filter(|path| !moved_to.contains(path) && !unreadable_b.contains(path))
+        .collect();
+
+    Ok(report)
+}
+
+/// Runs the `diff` subcommand
+/// Returns true when both trees are identical
+pub fn run(options: &DiffOptions) -> Result<bool, Box<dyn Error>> {
+    for dir in [&options.dir_a, &options.dir_b] {
+        if !dir.is_dir() {
+            return Err(format!("'{}' is not a directory", dir.display()).into());
+        }
+    }
+
+    let report = compare(&options.dir_a, &options.dir_b)?;
+
+    for path in &report.identical {
+        println!("= {}", path);
+    }
+    for path in &report.modified {
+        println!("M {}", path);
+    }
+    for (from, to) in &report.moved {
+        println!("R {} -> {}", from, to);
+    }
+    for path in &report.only_a {
+        println!("- {}", path);
+    }
+    for path in &report.only_b {
+        println!("+ {}", path);
+    }
+    for path in &report.unreadable {
+        println!("! {}", path.display());
+    }
+
+    println!(
+        "Identical: {}, modified: {}, moved: {}, only in A: {}, only in B: {}, unreadable: {}",
+        report.identical.len(),
+        report.modified.len(),
+        report.moved.len(),
+        report.only_a.len(),
+        report.only_b.len(),
+        report.unreadable.len()
+    );
+    Ok(report.is_identical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn parse_args(args: &[String]) -> Result<DiffOptions, String> {
+        crate::cli::try_parse(args)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args: Vec<String> = vec!["a".into(), "b".into()];
+        let options = parse_args(&args).unwrap();
+        assert_eq!(options.dir_a, PathBuf::from("a"));
+        assert_eq!(options.dir_b, PathBuf::from("b"));
+
+        assert!(parse_args(&args[..1]).is_err());
+        assert!(parse_args(&["a".into(), "--fast".into()]).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        let write = |root: &Path, name: &str, content: &[u8]| {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write(a.path(), "same.txt", b"same");
+        write(b.path(), "same.txt", b"same");
+        write(a.path(), "changed.txt", b"version 1");
+        write(b.path(), "changed.txt", b"version 2");
+        write(a.path(), "old/name.bin", b"moved content");
+        write(b.path(), "new/name.bin", b"moved content");
+        write(a.path(), "removed.txt", b"gone");
+        write(b.path(), "added.txt", b"new file");
+
+        let report = compare(a.path(), b.path()).unwrap();
+        assert_eq!(report.identical, vec!["same.txt"]);
+        assert_eq!(report.modified, vec!["changed.txt"]);
+        assert_eq!(
+            report.moved,
+            vec![("old/name.bin".to_string(), "new/name.bin".to_string())]
+        );
+        assert_eq!(report.only_a, vec!["removed.txt"]);
+        assert_eq!(report.only_b, vec!["added.txt"]);
+        assert!(!report.is_identical());
+
+        assert!(compare(a.path(), a.path()).unwrap().is_identical());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compare_symlinks() {
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("target"), b"shared").unwrap();
+        for root in [a.path(), b.path()] {
+            fs::write(root.join("file"), b"content").unwrap();
+            std::os::unix::fs::symlink(root.join("file"), root.join("alias")).unwrap();
+            std::os::unix::fs::symlink(outside.path().join("target"), root.join("link")).unwrap();
+        }
+
+        // Links are compared under their own names, even when they point
+        // outside the tree or to another file of it
+        let report = compare(a.path(), b.path()).unwrap();
+        assert_eq!(report.identical, vec!["alias", "file", "link"]);
+        assert!(report.is_identical());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compare_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        for root in [a.path(), b.path()] {
+            fs::write(root.join("same.txt"), b"same").unwrap();
+            fs::write(root.join("locked.txt"), b"data").unwrap();
+        }
+        let locked = b.path().join("locked.txt");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::File::open(&locked).is_ok() {
+            return; // Permissions do not apply (running as root)
+        }
+
+        // The unreadable file is listed as such, and the trees differ
+        let report = compare(a.path(), b.path()).unwrap();
+        assert_eq!(report.identical, vec!["same.txt"]);
+        assert!(report.modified.is_empty());
+        assert_eq!(report.unreadable, vec![locked]);
+        assert!(!report.is_identical());
+    }
+}
@@ -0,0 +1,421 @@
+// Import standard library components
+use std::{
+    collections::{BTreeMap, HashSet}, // Grouping and hard link tracking
+    error::Error,                     // Error trait for boxed errors
+    fs::{self, File},                 // File handling
+    io::{self, Read, Seek, SeekFrom, Write}, // Partial reads and prompts
+    path::{Path, PathBuf},            // Path manipulation
+    time::SystemTime,                 // Modification times
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+use sha2::{Digest, Sha256}; // Partial hash of first/last blocks
+
+use crate::hasher::hash_file;
+use crate::{format_size, parse_path, walk};
+
+/// Size of the blocks read at the start and end of a file for the partial hash
+const PARTIAL_BLOCK: u64 = 65_536;
+
+/// What to do with the duplicates of each set (the first path is always kept)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Report,
+    Hardlink,
+    Delete,
+}
+
+/// Options of the `dupes` subcommand
+pub struct DupesOptions {
+    pub roots: Vec<PathBuf>,
+    pub json: bool,
+    pub action: Action,
+}
+
+/// Arguments of `slashsum dupes`
+#[derive(Args, Debug)]
+pub struct DupesArgs {
+    /// Directories to search
+    #[arg(value_name = "DIR", required = true, value_parser = parse_path)]
+    roots: Vec<PathBuf>,
+
+    /// Print duplicate sets as JSON
+    #[arg(long, conflicts_with_all = ["hardlink", "delete"])]
+    json: bool,
+
+    /// Replace duplicates with hard links to the first file (asks per set)
+    #[arg(long, conflicts_with = "delete")]
+    hardlink: bool,
+
+    /// Delete duplicates, keeping the first file (asks per set)
+    #[arg(long)]
+    delete: bool,
+}
+
+impl DupesArgs {
+    /// Turns the action flags into an action
+    pub fn into_options(self) -> DupesOptions {
+        let action = if self.hardlink {
+            Action::Hardlink
+        } else if self.delete {
+            Action::Delete
+        } else {
+            Action::Report
+        };
+        DupesOptions {
+            roots: self.roots,
+            json: self.json,
+            action,
+        }
+    }
+}
+
+/// Files sharing the same content
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateSet {
+    pub size: u64,
+    pub sha256: String,
+    pub paths: Vec<PathBuf>, // Sorted; the first one is the copy that is kept
+}
+
+impl DuplicateSet {
+    /// Bytes that would be freed by keeping a single copy
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Hashes the first and last blocks of a file
+/// Files of up to two blocks are hashed entirely
+fn partial_hash(path: &Path, size: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; PARTIAL_BLOCK as usize];
+
+    let head = size.min(PARTIAL_BLOCK) as usize;
+    file.read_exact(&mut buffer[..head])?;
+    hasher.update(&buffer[..head]);
+
+    if size > PARTIAL_BLOCK {
+        let tail_start = size.saturating_sub(PARTIAL_BLOCK).max(PARTIAL_BLOCK);
+        let tail = (size - tail_start) as usize;
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.read_exact(&mut buffer[..tail])?;
+        hasher.update(&buffer[..tail]);
+    }
+
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Finds sets of identical files
+/// Candidates are narrowed by size, then by a partial hash, and only the
+/// remaining ones are fully hashed; hard links to the same file count once
+/// Files that vanish or cannot be read are skipped with a warning
+pub fn find_duplicates(files: &[PathBuf]) -> Result<Vec<DuplicateSet>, Box<dyn Error>> {
+    // Stage 1: group by size (empty files are never reported)
+    let mut seen_ids = HashSet::new();
+    let mut by_size: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for file in files {
+        let metadata = match fs::metadata(file) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("Warning: {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        if metadata.len() == 0 {
+            continue;
+        }
+        if let Some(id) = walk::file_id(&metadata)
+            && !seen_ids.insert(id)
+        {
+            continue;
+        }
+        by_size
+            .entry(metadata.len())
+            .or_default()
+            .push(file.clone());
+    }
+
+    let mut sets = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, p)| p.len() > 1) {
+        // Stage 2: group by partial hash
+        let mut by_partial: BTreeMap<Vec<u8>, Vec<PathBuf>> = BTreeMap::new();
+        for path in paths {
+            match partial_hash(&path, size) {
+                Ok(partial) => by_partial.entry(partial).or_default().push(path),
+                Err(e) => eprintln!("Warning: {}: {}", path.display(), e),
+            }
+        }
+
+        // Stage 3: confirm with the full digest from the hashing pipeline
+        for paths in by_partial.into_values().filter(|p| p.len() > 1) {
+            let mut by_digest: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+            for path in paths {
+                match hash_file(&path) {
+                    Ok(checksums) => by_digest.entry(checksums.sha256).or_default().push(path),
+                    Err(e) => eprintln!("Warning: {}: {}", path.display(), e),
+                }
+            }
+
+            for (sha256, mut paths) in by_digest.into_iter().filter(|(_, p)| p.len() > 1) {
+                paths.sort();
+                sets.push(DuplicateSet {
+                    size,
+                    sha256,
+                    paths,
+                });
+            }
+        }
+    }
+
+    // Largest waste first, then by path for a stable order
+    sets.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.paths.cmp(&b.paths)));
+    Ok(sets)
+}
+
+/// Asks a yes/no question on the terminal (default: no)
+fn confirm(question: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Replaces a duplicate with a hard link to the kept file
+/// The link is created next to the duplicate and renamed over it, so the
+/// duplicate is never missing if linking fails
+fn replace_with_hardlink(keep: &Path, duplicate: &Path) -> io::Result<()> {
+    let file_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = duplicate.with_file_name(format!(".{}.slashsum-link", file_name));
+    fs::hard_link(keep, &temporary)?;
+    fs::rename(&temporary, duplicate).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// Applies the requested action to one set after confirmation
+fn apply_action(
+    set: &DuplicateSet,
+    action: Action,
+    hashed_at: &BTreeMap<PathBuf, Option<SystemTime>>,
+) -> io::Result<u64> {
+    let verb = if action == Action::Hardlink {
+        "Hardlink"
+    } else {
+        "Delete"
+    };
+    let question = format!(
+        "{} {} duplicate(s) of {}?",
+        verb,
+        set.paths.len() - 1,
+        set.paths[0].display()
+    );
+    if !confirm(&question)? {
+        return Ok(0);
+    }
+    Ok(act_on_set(set, action, hashed_at))
+}
+
+/// Deletes or hard-links the duplicates of a set, and returns the bytes freed
+/// Files modified since they were hashed, vanished, or that cannot be
+/// replaced (a hard link across file systems) are reported and left untouched
+fn act_on_set(
+    set: &DuplicateSet,
+    action: Action,
+    hashed_at: &BTreeMap<PathBuf, Option<SystemTime>>,
+) -> u64 {
+    let unchanged = |path: &PathBuf| -> Result<(), String> {
+        let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+        if metadata.len() == set.size && metadata.modified().ok() == hashed_at[path] {
+            Ok(())
+        } else {
+            Err("modified since hashing".to_string())
+        }
+    };
+    if let Err(reason) = unchanged(&set.paths[0]) {
+        eprintln!("Skipped ({}): {}", reason, set.paths[0].display());
+        return 0;
+    }
+
+    let mut freed = 0;
+    for duplicate in &set.paths[1..] {
+        if let Err(reason) = unchanged(duplicate) {
+            eprintln!("Skipped ({}): {}", reason, duplicate.display());
+            continue;
+        }
+        let result = match action {
+            Action::Hardlink => replace_with_hardlink(&set.paths[0], duplicate),
+            Action::Delete => fs::remove_file(duplicate),
+            Action::Report => continue,
+        };
+        match result {
+            Ok(()) => freed += set.size,
+            Err(e) => eprintln!("Skipped ({}): {}", e, duplicate.display()),
+        }
+    }
+    freed
+}
+
+/// Runs the `dupes` subcommand
+pub fn run(options: &DupesOptions) -> Result<(), Box<dyn Error>> {
+    let mut files = Vec::new();
+    for root in &options.roots {
+        files.extend(walk::collect_files(root)?);
+    }
+    files.sort();
+    files.dedup();
+
+    // Remember modification times to detect changes before acting
+    let hashed_at: BTreeMap<PathBuf, Option<SystemTime>> = files
+        .iter()
+        .map(|f| (f.clone(), fs::metadata(f).and_then(|m| m.modified()).ok()))
+        .collect();
+
+    let sets = find_duplicates(&files)?;
+    let wasted: u64 = sets.iter().map(DuplicateSet::wasted).sum();
+
+    if options.json {
+        let json_sets: Vec<serde_json::Value> = sets
+            .iter()
+            .map(|set| {
+                serde_json::json!({
+                    "size": set.size,
+                    "sha256": set.sha256,
+                    "wasted": set.wasted(),
+                    "paths": set.paths.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let report = serde_json::json!({ "sets": json_sets, "wasted": wasted });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let mut freed = 0;
+    for (index, set) in sets.iter().enumerate() {
+        println!(
+            "Duplicate set {}: {} files of {}, {} wasted",
+            index + 1,
+            set.paths.len(),
+            format_size(set.size),
+            format_size(set.wasted())
+        );
+        println!("  SHA256: {}", set.sha256);
+        for path in &set.paths {
+            println!("  {}", path.display());
+        }
+        if options.action != Action::Report {
+            freed += apply_action(set, options.action, &hashed_at)?;
+        }
+        println!();
+    }
+
+    println!(
+        "Duplicate sets: {}, wasted: {}",
+        sets.len(),
+        format_size(wasted)
+    );
+    if options.action != Action::Report {
+        println!("Freed: {}", format_size(freed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_args(args: &[String]) -> Result<DupesOptions, String> {
+        Ok(crate::cli::try_parse::<DupesArgs>(args)?.into_options())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&["a", "b", "--hardlink"])).unwrap();
+        assert_eq!(options.roots.len(), 2);
+        assert_eq!(options.action, Action::Hardlink);
+
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["a", "--hardlink", "--delete"])).is_err());
+        assert!(parse_args(&args(&["a", "--json", "--delete"])).is_err());
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+
+        // Same size and same first/last blocks, different middle
+        let mut big = vec![7u8; 3 * PARTIAL_BLOCK as usize];
+        fs::write(path("big1"), &big).unwrap();
+        fs::write(path("big2"), &big).unwrap();
+        big[PARTIAL_BLOCK as usize + 10] = 8;
+        fs::write(path("big3"), &big).unwrap();
+
+        fs::write(path("small1"), b"same").unwrap();
+        fs::write(path("small2"), b"same").unwrap();
+        fs::write(path("small3"), b"diff").unwrap();
+        fs::write(path("empty1"), b"").unwrap();
+        fs::write(path("empty2"), b"").unwrap();
+
+        let mut files = walk::collect_files(dir.path()).unwrap();
+        // A file that vanished after the walk is skipped
+        files.push(path("vanished"));
+        let sets = find_duplicates(&files).unwrap();
+
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].paths, vec![path("big1"), path("big2")]);
+        assert_eq!(sets[0].wasted(), 3 * PARTIAL_BLOCK);
+        assert_eq!(sets[1].paths, vec![path("small1"), path("small2")]);
+        assert_eq!(sets[1].size, 4);
+    }
+
+    #[test]
+    #[cfg(unix)] // Hard links are only recognized through inode numbers
+    fn test_replace_with_hardlink() {
+        let dir = tempdir().unwrap();
+        let keep = dir.path().join("keep");
+        let duplicate = dir.path().join("duplicate");
+        fs::write(&keep, b"content").unwrap();
+        fs::write(&duplicate, b"content").unwrap();
+
+        replace_with_hardlink(&keep, &duplicate).unwrap();
+
+        let files = walk::collect_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(find_duplicates(&files).unwrap().is_empty()); // Now the same inode
+    }
+
+    #[test]
+    fn test_act_on_set_skips_vanished() {
+        let dir = tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a", "b", "c"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for path in &paths {
+            fs::write(path, b"content").unwrap();
+        }
+        let hashed_at = paths
+            .iter()
+            .map(|path| (path.clone(), fs::metadata(path).unwrap().modified().ok()))
+            .collect();
+        let set = find_duplicates(&paths).unwrap().remove(0);
+
+        // A duplicate removed after hashing is skipped, the others are handled
+        fs::remove_file(&paths[1]).unwrap();
+        assert_eq!(act_on_set(&set, Action::Delete, &hashed_at), 7);
+        assert_eq!(
+            walk::collect_files(dir.path()).unwrap(),
+            vec![paths[0].clone()]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// Import standard library components
+use std::{
+    error::Error, // Error trait and source chain
+    fmt,          // Display implementation
+    io,           // I/O error kinds
+    path::Path,   // File named in messages
+};
+
+use crate::interrupt;
+
+/// Why a run failed, each case with its own exit code
+#[derive(Debug, PartialEq, Eq)]
+pub enum SlashsumError {
+    Usage(String),      // Invalid option or combination of options
+    NotFound(String),   // Input, manifest or known file missing
+    Permission(String), // Input or output not accessible
+    Read(String),       // Read or write error, invalid file content
+    Mismatch,           // Digests differ, results already printed
+    Changed,            // A file changed while being hashed
+    Interrupted,        // Stopped by Ctrl-C or SIGTERM
+}
+
+impl SlashsumError {
+    /// Classifies an I/O error by its kind, with what was being done
+    pub fn io(context: String, error: &io::Error) -> Self {
+        Self::with_kind(error.kind(), format!("{}: {}", context, error))
+    }
+
+    /// Classifies an error about one input file, naming the file unless the
+    /// message already does
+    pub fn for_file(path: &Path, error: Box<dyn Error>) -> Self {
+        let name = path.display().to_string();
+        let message = error.to_string();
+        if message.contains(&name) {
+            return Self::from(error);
+        }
+        match error.downcast::<SlashsumError>() {
+            Ok(error) => *error,
+            Err(error) => Self::classify(&*error, format!("'{}': {}", name, message)),
+        }
+    }
+
+    /// Interrupted when the chain holds the interruption, otherwise decided
+    /// by its first I/O error
+    fn classify(error: &(dyn Error + 'static), message: String) -> Self {
+        let mut source = Some(error);
+        while let Some(cause) = source {
+            if cause.is::<interrupt::Interrupted>() {
+                return SlashsumError::Interrupted;
+            }
+            if let Some(io_error) = cause.downcast_ref::<io::Error>() {
+                return Self::with_kind(io_error.kind(), message);
+            }
+            source = cause.source();
+        }
+        SlashsumError::Read(message)
+    }
+
+    /// Not found, permission denied, or any other read or write error
+    fn with_kind(kind: io::ErrorKind, message: String) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => SlashsumError::NotFound(message),
+            io::ErrorKind::PermissionDenied => SlashsumError::Permission(message),
+            _ => SlashsumError::Read(message),
+        }
+    }
+
+    /// Status the process exits with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SlashsumError::Mismatch => 1,
+            SlashsumError::Usage(_) => 2, // Same as clap's own usage errors
+            SlashsumError::Changed => 3,
+            SlashsumError::NotFound(_) => 4,
+            SlashsumError::Permission(_) => 5,
+            SlashsumError::Read(_) => 6,
+            SlashsumError::Interrupted => interrupt::EXIT_CODE,
+        }
+    }
+
+    /// Message printed after "slashsum: error: ", None when the output
+    /// already told what happened
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            SlashsumError::Usage(message)
+            | SlashsumError::NotFound(message)
+            | SlashsumError::Permission(message)
+            | SlashsumError::Read(message) => Some(message),
+            SlashsumError::Mismatch | SlashsumError::Changed | SlashsumError::Interrupted => None,
+        }
+    }
+}
+
+impl fmt::Display for SlashsumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlashsumError::Mismatch => write!(f, "checksum mismatch"),
+            SlashsumError::Changed => write!(f, "file changed while being hashed"),
+            SlashsumError::Interrupted => write!(f, "interrupted"),
+            _ => write!(f, "{}", self.message().unwrap_or_default()),
+        }
+    }
+}
+
+impl Error for SlashsumError {}
+
+/// Errors from the hashing pipeline and subcommands keep their type when
+/// they have one
+impl From<Box<dyn Error>> for SlashsumError {
+    fn from(error: Box<dyn Error>) -> Self {
+        match error.downcast::<SlashsumError>() {
+            Ok(error) => *error,
+            Err(error) => Self::classify(&*error, error.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for SlashsumError {
+    fn from(error: io::Error) -> Self {
+        Self::with_kind(error.kind(), error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            SlashsumError::io("Cannot read 'a'".to_string(), &missing),
+            SlashsumError::NotFound(message) if message.starts_with("Cannot read 'a': ")
+        ));
+        let denied: Box<dyn Error> = Box::new(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(SlashsumError::from(denied).exit_code(), 5);
+        let broken = io::Error::other("I/O error");
+        assert_eq!(SlashsumError::from(broken).exit_code(), 6);
+
+        // Typed errors survive a trip through Box<dyn Error>
+        let boxed: Box<dyn Error> = Box::new(SlashsumError::Usage("bad".to_string()));
+        assert_eq!(
+            SlashsumError::from(boxed),
+            SlashsumError::Usage("bad".to_string())
+        );
+        let boxed: Box<dyn Error> = Box::new(interrupt::Interrupted { bytes: 1 });
+        assert_eq!(SlashsumError::from(boxed), SlashsumError::Interrupted);
+        let boxed: Box<dyn Error> = "Thread MD5 error".into();
+        assert_eq!(SlashsumError::from(boxed).exit_code(), 6);
+
+        let boxed: Box<dyn Error> = Box::new(io::Error::from(io::ErrorKind::PermissionDenied));
+        match SlashsumError::for_file(Path::new("/root/a"), boxed) {
+            SlashsumError::Permission(message) => assert!(message.starts_with("'/root/a': ")),
+            error => panic!("unexpected {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(SlashsumError::Mismatch.message(), None);
+        assert_eq!(SlashsumError::Changed.exit_code(), 3);
+        assert_eq!(SlashsumError::Interrupted.exit_code(), 130);
+        let error = SlashsumError::NotFound("File 'a' not found".to_string());
+        assert_eq!(error.to_string(), "File 'a' not found");
+    }
+}
@@ -0,0 +1,244 @@
+// Import standard library components
+use std::{
+    collections::{HashMap, HashSet}, // Digest lookup and hard link tracking
+    error::Error,                    // Error trait for boxed errors
+    fs,                              // File system access
+    io,                              // Empty reader
+    path::PathBuf,                   // Path manipulation
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::error::SlashsumError;
+use crate::hasher::{Algorithm, hash_file, hash_reader, parse_digest};
+use crate::{manifest, parse_path, walk};
+
+/// Options of the `find` subcommand
+pub struct FindOptions {
+    pub roots: Vec<PathBuf>,  // Directories (or files) to search
+    pub digests: Vec<String>, // Wanted digests, lower-case hexadecimal
+    pub size: Option<u64>,    // Only consider files of exactly this size
+}
+
+/// Arguments of `slashsum find`
+#[derive(Args, Debug)]
+pub struct FindArgs {
+    /// Directories (or files) to search
+    #[arg(value_name = "DIR", required = true, value_parser = parse_path)]
+    roots: Vec<PathBuf>,
+
+    /// Digest to look for (repeatable, any supported algorithm)
+    #[arg(long = "hash", value_name = "HEX", value_parser = parse_digest)]
+    digests: Vec<String>,
+
+    /// Read digests from a file (one per line, or a manifest)
+    #[arg(long, value_name = "FILE", value_parser = parse_path)]
+    hashes_file: Vec<PathBuf>,
+
+    /// Only read files of exactly this size
+    #[arg(long, value_name = "BYTES")]
+    size: Option<u64>,
+}
+
+impl FindArgs {
+    /// Reads the hashes files and checks that there is something to find
+    pub fn into_options(self) -> Result<FindOptions, SlashsumError> {
+        let mut digests = self.digests;
+        for path in &self.hashes_file {
+            let content = fs::read_to_string(path)
+                .map_err(|e| SlashsumError::io(format!("Cannot read '{}'", path.display()), &e))?;
+            digests.extend(read_digests(&content).map_err(SlashsumError::Usage)?);
+        }
+        if digests.is_empty() {
+            return Err(SlashsumError::Usage(
+                "find requires --hash or --hashes-file".to_string(),
+            ));
+        }
+
+        Ok(FindOptions {
+            roots: self.roots,
+            digests,
+            size: self.size,
+        })
+    }
+}
+
+/// Extracts digests from a hashes file
+/// Each line is either a bare digest (anything after it is ignored) or a
+/// manifest line; empty lines and '#' comments are skipped
+fn read_digests(content: &str) -> Result<Vec<String>, String> {
+    let mut digests = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match manifest::parse_line(line) {
+            Some((_, _, digest)) => digests.push(digest),
+            None => {
+                let token = line.split_whitespace().next().unwrap_or(line);
+                digests.push(parse_digest(token)?);
+            }
+        }
+    }
+    Ok(digests)
+}
+
+/// Walks the roots and prints every file whose digest matches a wanted one
+/// Files are skipped without being read when their size cannot match, and hard
+/// links to an already hashed file reuse its result
+/// Returns true if at least one file matched
+pub fn run(options: &FindOptions) -> Result<bool, Box<dyn Error>> {
+    let wanted: HashSet<&str> = options.digests.iter().map(String::as_str).collect();
+
+    // Empty files only need to be considered when an empty-input digest is wanted
+    let empty = hash_reader(io::empty())?;
+    let empty_wanted = Algorithm::ALL
+        .into_iter()
+        .any(|algo| wanted.contains(empty.get(algo)));
+
+    let mut hashed: HashMap<(u64, u64), Vec<String>> = HashMap::new();
+    let (mut scanned, mut read, mut matches) = (0usize, 0usize, 0usize);
+
+    for root in &options.roots {
+        // Unreadable directories are skipped like unreadable files
+        let files = match walk::collect_files(root) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Warning: {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        for file in files {
+            let metadata = match fs::metadata(&file) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    eprintln!("Warning: {}: {}", file.display(), e);
+                    continue;
+                }
+            };
+            scanned += 1;
+
+            // Cheap prefilters based on metadata only
+            if options.size.is_some_and(|size| size != metadata.len()) {
+                continue;
+            }
+            if metadata.len() == 0 && !empty_wanted {
+                continue;
+            }
+
+            let id = walk::file_id(&metadata);
+            let found = match id.and_then(|id| hashed.get(&id)) {
+                Some(found) => found.clone(),
+                None => {
+                    let checksums = match hash_file(&file) {
+                        Ok(checksums) => checksums,
+                        Err(e) => {
+                            eprintln!("Warning: {}: {}", file.display(), e);
+                            continue;
+                        }
+                    };
+                    read += 1;
+
+                    let found: Vec<String> = Algorithm::ALL
+                        .into_iter()
+                        .map(|algo| checksums.get(algo))
+                        .filter(|digest| wanted.contains(digest))
+                        .map(str::to_string)
+                        .collect();
+                    if let Some(id) = id {
+                        hashed.insert(id, found.clone());
+                    }
+                    found
+                }
+            };
+
+            for digest in found {
+                println!("{}  {}", digest, file.display());
+                matches += 1;
+            }
+        }
+    }
+
+    println!(
+        "Scanned: {} files, hashed: {}, matches: {}",
+        scanned, read, matches
+    );
+    Ok(matches > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_args(args: &[String]) -> Result<FindOptions, String> {
+        crate::cli::try_parse::<FindArgs>(args)?
+            .into_options()
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_read_digests() {
+        let content = "# wanted files\n\
+                       900150983cd24fb0d6963f7d28e17f72\n\
+                       \n\
+                       a9993e364706816aba3e25717850c26c9cd0d89d  abc.txt\n\
+                       SHA1 (x) = A9993E364706816ABA3E25717850C26C9CD0D89D\n";
+        assert_eq!(
+            read_digests(content).unwrap(),
+            vec![
+                "900150983cd24fb0d6963f7d28e17f72",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ]
+        );
+        assert!(read_digests("not-a-digest\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&[
+            "dir",
+            "--hash",
+            "900150983cd24fb0d6963f7d28e17f72",
+            "--size",
+            "3",
+        ]))
+        .unwrap();
+        assert_eq!(options.roots, vec![PathBuf::from("dir")]);
+        assert_eq!(options.size, Some(3));
+
+        assert!(parse_args(&args(&["dir"])).is_err());
+        assert!(parse_args(&args(&["--hash", "900150983cd24fb0d6963f7d28e17f72"])).is_err());
+        assert!(parse_args(&args(&["dir", "--size", "big"])).is_err());
+    }
+
+    #[test]
+    fn test_run_finds_matching_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested").join("abc.txt"), b"abc").unwrap();
+        fs::write(dir.path().join("other.txt"), b"other").unwrap();
+        fs::write(dir.path().join("empty"), b"").unwrap();
+
+        let mut options = FindOptions {
+            roots: vec![dir.path().to_path_buf()],
+            digests: vec!["900150983cd24fb0d6963f7d28e17f72".to_string()],
+            size: None,
+        };
+        assert!(run(&options).unwrap());
+
+        // A missing root is skipped with a warning
+        options.roots.insert(0, dir.path().join("missing"));
+        assert!(run(&options).unwrap());
+
+        // A size prefilter that excludes the only match
+        options.size = Some(5);
+        assert!(!run(&options).unwrap());
+    }
+}
@@ -0,0 +1,335 @@
+// Import standard library components
+use std::{
+    error::Error,                                       // Error trait for boxed errors
+    fs::{self, File},                                   // Source, image and log files
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write}, // Recovering reads
+    path::{Path, PathBuf},                              // Path manipulation
+    thread,                                             // Whole-image hashing thread
+};
+
+// External crates
+use crossbeam_channel::bounded; // Copy of every byte read
+
+use crate::archive::{ChannelReader, TeeReader};
+use crate::error::SlashsumError;
+use crate::hasher::{Checksums, HashState, expected_size, hash_reader, hash_segment, special_kind};
+use crate::interrupt;
+
+/// Unit re-read after a failed read, and zero-filled when it cannot be read
+const SECTOR: usize = 512;
+
+/// Extra attempts of a failed read before giving up
+const RETRIES: u32 = 3;
+
+/// Default size of the segments hashed separately (1GiB)
+pub const SEGMENT_SIZE: u64 = 1 << 30;
+
+/// Options of `--forensic`
+pub struct ForensicOptions {
+    pub segment_size: u64,
+    pub image: Option<PathBuf>, // Copy of the data read, unreadable sectors zeroed
+    pub bad_blocks: Option<PathBuf>, // Log of the unreadable ranges
+}
+
+/// Unreadable range, replaced by zeros in the digests and the image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadRange {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Digests of one segment of the source
+pub struct Segment {
+    pub offset: u64,
+    pub checksums: Checksums,
+}
+
+/// Result of an acquisition
+pub struct Acquisition {
+    pub checksums: Checksums, // Whole source
+    pub segments: Vec<Segment>,
+    pub bad: Vec<BadRange>,
+}
+
+/// Reader that survives read errors: failed reads are retried, then re-read
+/// sector by sector, and sectors that still fail are zero-filled and logged
+/// (like dd conv=noerror,sync); everything returned is also written to the image
+struct RecoveringReader<R> {
+    inner: R,
+    offset: u64,
+    size: u64,
+    bad: Vec<BadRange>,
+    log: Option<File>,
+    image: Option<BufWriter<File>>,
+}
+
+impl<R: Read + Seek> RecoveringReader<R> {
+    /// Reads at a given offset, retrying failed reads
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .inner
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| self.inner.read(buf));
+            match result {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) if attempt < RETRIES => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Reads a region sector by sector, zero-filling the unreadable sectors
+    fn read_sectors(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let wanted = buf.len().min((self.size - self.offset) as usize);
+        let mut filled = 0;
+        while filled < wanted {
+            let end = (filled + SECTOR).min(wanted);
+            let position = self.offset + filled as u64;
+            match self.read_at(position, &mut buf[filled..end]) {
+                Ok(0) => break, // Shorter than its reported size
+                Ok(bytes) => filled += bytes,
+                Err(e) => {
+                    buf[filled..end].fill(0);
+                    self.record_bad(position, (end - filled) as u64, &e)?;
+                    filled = end;
+                }
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Adds an unreadable range, merged with the previous one when contiguous
+    fn record_bad(&mut self, offset: u64, length: u64, error: &io::Error) -> io::Result<()> {
+        if let Some(log) = &mut self.log {
+            writeln!(log, "{}\t{}\t{}", offset, length, error)?;
+            log.flush()?;
+        }
+        match self.bad.last_mut() {
+            Some(last) if last.offset + last.length == offset => last.length += length,
+            _ => self.bad.push(BadRange { offset, length }),
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for RecoveringReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset >= self.size {
+            return Ok(0);
+        }
+        let bytes = match self.read_at(self.offset, buf) {
+            Ok(bytes) => bytes,
+            Err(_) => self.read_sectors(buf)?,
+        };
+        if let Some(image) = &mut self.image {
+            image.write_all(&buf[..bytes])?;
+        }
+        self.offset += bytes as u64;
+        Ok(bytes)
+    }
+}
+
+/// Hashes a regular file or block device, tolerating read errors
+/// The whole source and each segment get their own digests, from a single read
+pub fn acquire(path: &Path, options: &ForensicOptions) -> Result<Acquisition, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() && special_kind(&metadata) != Some("block device") {
+        return Err(format!(
+            "--forensic needs a regular file or a block device: '{}'",
+            path.display()
+        )
+        .into());
+    }
+    let size = expected_size(&file)?.unwrap_or(0);
+
+    let image = match &options.image {
+        Some(image) => Some(BufWriter::new(File::create(image).map_err(|e| {
+            SlashsumError::io(format!("Cannot create image '{}'", image.display()), &e)
+        })?)),
+        None => None,
+    };
+    let log = match &options.bad_blocks {
+        Some(log) => Some(fs::File::create(log)?),
+        None => None,
+    };
+
+    let reader = RecoveringReader {
+        inner: file,
+        offset: 0,
+        size,
+        bad: Vec::new(),
+        log,
+        image,
+    };
+    acquire_from(reader, options.segment_size)
+}
+
+/// Runs an acquisition from a recovering reader
+fn acquire_from<R: Read + Seek>(
+    reader: RecoveringReader<R>,
+    segment_size: u64,
+) -> Result<Acquisition, Box<dyn Error>> {
+    // Whole-source digests are computed by another thread from the same read
+    let (sender, receiver) = bounded(16);
+    let whole_hasher =
+        thread::spawn(move || hash_reader(ChannelReader::new(receiver)).map_err(|e| e.to_string()));
+    let mut tee = TeeReader::new(reader, sender);
+
+    let mut segments = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (state, bytes) = hash_segment(
+            &mut tee,
+            HashState::new(),
+            Some(segment_size),
+            interrupt::flag(),
+        )?;
+        if bytes == 0 {
+            break;
+        }
+        segments.push(Segment {
+            offset,
+            checksums: state.finish(bytes),
+        });
+        offset += bytes;
+        if bytes < segment_size {
+            break;
+        }
+    }
+
+    let mut reader = tee.into_inner(); // Ends the whole-source digests
+    if let Some(image) = &mut reader.image {
+        image.flush()?;
+    }
+    let checksums = whole_hasher.join().map_err(|_| "Thread forensic error")??;
+    Ok(Acquisition {
+        checksums,
+        segments,
+        bad: reader.bad,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::ops::Range;
+    use tempfile::tempdir;
+
+    /// Disk whose reads fail when they touch a bad range, unless `flaky`
+    /// still allows them after that many failures
+    struct FaultyDisk {
+        data: Cursor<Vec<u8>>,
+        bad: Range<u64>,
+        flaky: Option<u32>,
+        failures: u32,
+    }
+
+    impl Read for FaultyDisk {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let start = self.data.position();
+            let end = start + buf.len() as u64;
+            if start < self.bad.end && self.bad.start < end {
+                self.failures += 1;
+                if self.flaky.is_none_or(|limit| self.failures <= limit) {
+                    return Err(io::Error::other("I/O error"));
+                }
+            }
+            self.data.read(buf)
+        }
+    }
+
+    impl Seek for FaultyDisk {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.data.seek(position)
+        }
+    }
+
+    fn reader(disk: FaultyDisk, image: Option<File>) -> RecoveringReader<FaultyDisk> {
+        RecoveringReader {
+            size: disk.data.get_ref().len() as u64,
+            inner: disk,
+            offset: 0,
+            bad: Vec::new(),
+            log: None,
+            image: image.map(BufWriter::new),
+        }
+    }
+
+    fn data() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i % 251) as u8 + 1).collect()
+    }
+
+    #[test]
+    fn test_bad_sectors_are_zero_filled() {
+        let dir = tempdir().unwrap();
+        let image = dir.path().join("image.dd");
+        let disk = FaultyDisk {
+            data: Cursor::new(data()),
+            bad: 1100..1200, // Within the third sector
+            flaky: None,
+            failures: 0,
+        };
+
+        let acquisition =
+            acquire_from(reader(disk, Some(File::create(&image).unwrap())), 4096).unwrap();
+        assert_eq!(
+            acquisition.bad,
+            vec![BadRange {
+                offset: 1024,
+                length: 512
+            }]
+        );
+
+        let mut expected = data();
+        expected[1024..1536].fill(0);
+        assert_eq!(fs::read(&image).unwrap(), expected);
+        assert_eq!(acquisition.checksums, hash_reader(&expected[..]).unwrap());
+
+        // Segments of 4096 bytes: 4096 + 4096 + 1808
+        let sizes: Vec<u64> = acquisition
+            .segments
+            .iter()
+            .map(|segment| segment.checksums.size)
+            .collect();
+        assert_eq!(sizes, vec![4096, 4096, 1808]);
+        assert_eq!(acquisition.segments[2].offset, 8192);
+        assert_eq!(
+            acquisition.segments[1].checksums,
+            hash_reader(&expected[4096..8192]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retried_reads_lose_nothing() {
+        let disk = FaultyDisk {
+            data: Cursor::new(data()),
+            bad: 0..10_000,
+            flaky: Some(RETRIES), // Succeeds on the last attempt
+            failures: 0,
+        };
+        let acquisition = acquire_from(reader(disk, None), SEGMENT_SIZE).unwrap();
+        assert!(acquisition.bad.is_empty());
+        assert_eq!(acquisition.checksums, hash_reader(&data()[..]).unwrap());
+    }
+
+    #[test]
+    fn test_acquire_file() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("evidence.bin");
+        fs::write(&input, data()).unwrap();
+        let options = ForensicOptions {
+            segment_size: SEGMENT_SIZE,
+            image: None,
+            bad_blocks: None,
+        };
+
+        let acquisition = acquire(&input, &options).unwrap();
+        assert_eq!(acquisition.segments.len(), 1);
+        assert_eq!(acquisition.checksums, hash_reader(&data()[..]).unwrap());
+        assert!(acquire(dir.path(), &options).is_err());
+    }
+}
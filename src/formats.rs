@@ -0,0 +1,460 @@
+// Import standard library components
+use std::{
+    error::Error,                         // Error trait for boxed errors
+    fs::File,                             // File handling
+    io::{self, BufRead, BufReader, Read}, // Streaming readers
+    path::Path,                           // Path manipulation
+};
+
+// External crates
+use crc::{CRC_32_ISO_HDLC, Crc}; // Same CRC-32 as the main pipeline
+
+use crate::hasher::CHUNK_SIZE;
+
+/// CRC-32/ISO-HDLC, used by zip, gzip and PNG alike
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// PNG file signature
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Formats whose internal CRCs can be checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    Gzip,
+    Png,
+}
+
+impl Format {
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Zip => "ZIP",
+            Format::Gzip => "gzip",
+            Format::Png => "PNG",
+        }
+    }
+
+    /// Detects a format from its magic number
+    fn detect(header: &[u8]) -> Option<Format> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(Format::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Format::Gzip)
+        } else if header.starts_with(PNG_SIGNATURE) {
+            Some(Format::Png)
+        } else {
+            None
+        }
+    }
+
+    /// Name of the units carrying a CRC
+    fn units(self) -> &'static str {
+        match self {
+            Format::Zip => "entries",
+            Format::Gzip => "members",
+            Format::Png => "chunks",
+        }
+    }
+}
+
+/// Result of checking the internal CRCs of a file
+#[derive(Debug)]
+pub struct FormatReport {
+    pub format: Format,
+    pub checked: usize,      // Entries, members or chunks checked
+    pub errors: Vec<String>, // One line per corrupt entry, member or chunk
+}
+
+impl FormatReport {
+    /// Line printed after the checksums
+    pub fn describe(&self) -> String {
+        if self.errors.is_empty() {
+            format!(
+                "{}, {} {} checked, all CRCs OK",
+                self.format.name(),
+                self.checked,
+                self.format.units()
+            )
+        } else {
+            format!(
+                "{}, {} {} checked, {} corrupt",
+                self.format.name(),
+                self.checked,
+                self.format.units(),
+                self.errors.len()
+            )
+        }
+    }
+}
+
+/// Describes a CRC mismatch
+fn mismatch(stored: u32, computed: u32) -> String {
+    format!(
+        "CRC32 mismatch (stored {:08x}, computed {:08x})",
+        stored, computed
+    )
+}
+
+/// Reads a big-endian u32, or None at the end of the stream
+fn read_u32_be<R: Read>(reader: &mut R) -> io::Result<Option<u32>> {
+    let mut bytes = [0; 4];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(u32::from_be_bytes(bytes))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks the CRC of every PNG chunk (computed over the chunk type and data)
+fn check_png<R: Read>(mut reader: R, report: &mut FormatReport) -> io::Result<()> {
+    let mut signature = [0; 8];
+    reader.read_exact(&mut signature)?;
+    let mut offset = signature.len() as u64;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    while let Some(length) = read_u32_be(&mut reader)? {
+        let mut chunk_type = [0; 4];
+        reader.read_exact(&mut chunk_type)?;
+        let name = String::from_utf8_lossy(&chunk_type).into_owned();
+        let mut digest = CRC32.digest();
+        digest.update(&chunk_type);
+
+        let mut remaining = length as usize;
+        while remaining > 0 {
+            let size = remaining.min(buffer.len());
+            reader.read_exact(&mut buffer[..size]).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "chunk {} ({}) at offset {} is truncated",
+                        report.checked + 1,
+                        name,
+                        offset
+                    ),
+                )
+            })?;
+            digest.update(&buffer[..size]);
+            remaining -= size;
+        }
+
+        let stored = read_u32_be(&mut reader)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "chunk {} ({}) at offset {} has no CRC",
+                    report.checked + 1,
+                    name,
+                    offset
+                ),
+            )
+        })?;
+        report.checked += 1;
+        let computed = digest.finalize();
+        if stored != computed {
+            report.errors.push(format!(
+                "chunk {} ({}) at offset {}: {}",
+                report.checked,
+                name,
+                offset,
+                mismatch(stored, computed)
+            ));
+        }
+
+        offset += 12 + length as u64;
+        if &chunk_type == b"IEND" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Skips the gzip member header, returning the original file name if stored
+fn read_gzip_header<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut header = [0; 10];
+    reader.read_exact(&mut header)?;
+    if header[..3] != [0x1f, 0x8b, 0x08] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid gzip header",
+        ));
+    }
+    let flags = header[3];
+
+    // FEXTRA: length-prefixed extra field
+    if flags & 0x04 != 0 {
+        let mut length = [0; 2];
+        reader.read_exact(&mut length)?;
+        io::copy(
+            &mut reader.take(u16::from_le_bytes(length) as u64),
+            &mut io::sink(),
+        )?;
+    }
+
+    // FNAME and FCOMMENT: zero-terminated strings
+    let mut name = None;
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let mut field = Vec::new();
+            reader.read_until(0, &mut field)?;
+            field.pop();
+            if flag == 0x08 {
+                name = Some(String::from_utf8_lossy(&field).into_owned());
+            }
+        }
+    }
+
+    // FHCRC: CRC16 of the header, not checked
+    if flags & 0x02 != 0 {
+        reader.read_exact(&mut [0; 2])?;
+    }
+    Ok(name)
+}
+
+/// Checks the CRC32 and ISIZE trailer of every gzip member
+fn check_gzip<R: BufRead>(mut reader: R, report: &mut FormatReport) -> io::Result<()> {
+    let mut buffer = vec![0; CHUNK_SIZE];
+    while !reader.fill_buf()?.is_empty() {
+        let member = report.checked + 1;
+        let name = read_gzip_header(&mut reader)?;
+        let label = match &name {
+            Some(name) => format!("member {} ({})", member, name),
+            None => format!("member {}", member),
+        };
+
+        // Decompress, computing the CRC and size of the original data
+        let mut decoder = flate2::bufread::DeflateDecoder::new(&mut reader);
+        let mut digest = CRC32.digest();
+        let mut size: u64 = 0;
+        loop {
+            let bytes = match decoder.read(&mut buffer) {
+                Ok(bytes) => bytes,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData
+                    ) =>
+                {
+                    // The end of a corrupt member, and so the next ones, cannot be found
+                    report.checked += 1;
+                    report
+                        .errors
+                        .push(format!("{}: deflate error ({})", label, e));
+                    return Ok(());
+                }
+                Err(e) => return Err(io::Error::new(e.kind(), format!("{}: {}", label, e))),
+            };
+            if bytes == 0 {
+                break;
+            }
+            digest.update(&buffer[..bytes]);
+            size += bytes as u64;
+        }
+
+        let mut trailer = [0; 8];
+        reader.read_exact(&mut trailer).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{}: missing trailer", label),
+            )
+        })?;
+        let stored_crc = u32::from_le_bytes(trailer[..4].try_into().unwrap_or_default());
+        let stored_size = u32::from_le_bytes(trailer[4..].try_into().unwrap_or_default());
+        let computed = digest.finalize();
+
+        report.checked += 1;
+        if stored_crc != computed {
+            report
+                .errors
+                .push(format!("{}: {}", label, mismatch(stored_crc, computed)));
+        }
+        // ISIZE is the original size modulo 2^32
+        if stored_size != size as u32 {
+            report.errors.push(format!(
+                "{}: ISIZE mismatch (stored {}, decompressed {})",
+                label, stored_size, size
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the CRC32 of every zip entry against its decompressed content
+fn check_zip(file: File, report: &mut FormatReport) -> Result<(), Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))?;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let stored = entry.crc32();
+
+        let mut digest = CRC32.digest();
+        let mut failure = None;
+        loop {
+            match entry.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(bytes) => digest.update(&buffer[..bytes]),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        report.checked += 1;
+        let computed = digest.finalize();
+        if stored != computed {
+            // The zip reader also rejects the entry at its end: our own CRC is more precise
+            report
+                .errors
+                .push(format!("entry '{}': {}", name, mismatch(stored, computed)));
+        } else if let Some(e) = failure {
+            report.errors.push(format!("entry '{}': {}", name, e));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the CRCs stored inside zip, gzip and PNG files
+/// Returns None for other formats
+pub fn check_format(path: &Path) -> Result<Option<FormatReport>, Box<dyn Error>> {
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, File::open(path)?);
+    let Some(format) = Format::detect(reader.fill_buf()?) else {
+        return Ok(None);
+    };
+
+    let mut report = FormatReport {
+        format,
+        checked: 0,
+        errors: Vec::new(),
+    };
+    match format {
+        Format::Png => check_png(reader, &mut report)?,
+        Format::Gzip => check_gzip(reader, &mut report)?,
+        Format::Zip => check_zip(File::open(path)?, &mut report)?,
+    }
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io::Write};
+    use tempfile::tempdir;
+
+    /// Builds a PNG chunk with a valid CRC
+    fn png_chunk(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(chunk_type);
+        chunk.extend_from_slice(data);
+        let mut digest = CRC32.digest();
+        digest.update(chunk_type);
+        digest.update(data);
+        chunk.extend_from_slice(&digest.finalize().to_be_bytes());
+        chunk
+    }
+
+    #[test]
+    fn test_check_png() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("image.png");
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(png_chunk(b"IHDR", &[0; 13]));
+        png.extend(png_chunk(b"IDAT", b"pixels"));
+        png.extend(png_chunk(b"IEND", b""));
+        fs::write(&path, &png).unwrap();
+
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!((report.format, report.checked), (Format::Png, 3));
+        assert!(report.errors.is_empty());
+
+        // Flip one byte of the IDAT data
+        png[8 + 25 + 8] ^= 0xff;
+        fs::write(&path, &png).unwrap();
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("chunk 2 (IDAT) at offset 33: CRC32 mismatch"));
+    }
+
+    #[test]
+    fn test_check_gzip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.gz");
+        let mut encoder = flate2::GzBuilder::new()
+            .filename("data.txt")
+            .write(Vec::new(), Default::default());
+        encoder.write_all(b"some data to compress").unwrap();
+        let mut gzip = encoder.finish().unwrap();
+        fs::write(&path, &gzip).unwrap();
+
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!((report.format, report.checked), (Format::Gzip, 1));
+        assert!(report.errors.is_empty());
+
+        // Corrupt the stored CRC and size
+        let length = gzip.len();
+        gzip[length - 8] ^= 0xff;
+        gzip[length - 1] ^= 0xff;
+        fs::write(&path, &gzip).unwrap();
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].starts_with("member 1 (data.txt): CRC32 mismatch"));
+        assert!(report.errors[1].starts_with("member 1 (data.txt): ISIZE mismatch"));
+
+        // A damaged deflate body is reported, and ends the check of the file
+        let mut first = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        first.write_all(b"first member").unwrap();
+        let mut gzip = first.finish().unwrap();
+        let second = gzip.len();
+        gzip.extend_from_within(..);
+        gzip.extend_from_within(..second);
+        gzip[second + 10] = 0xff; // Reserved block type
+        fs::write(&path, &gzip).unwrap();
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("member 2: deflate error"));
+    }
+
+    #[test]
+    fn test_check_zip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("archive.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("good.txt", stored).unwrap();
+        writer.write_all(b"good content").unwrap();
+        writer.start_file("bad.txt", stored).unwrap();
+        writer.write_all(b"bad content").unwrap();
+        writer.finish().unwrap();
+
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!((report.format, report.checked), (Format::Zip, 2));
+        assert!(report.errors.is_empty());
+
+        // Corrupt the stored data of the second entry
+        let mut zip = fs::read(&path).unwrap();
+        let position = zip
+            .windows(11)
+            .position(|window| window == b"bad content")
+            .unwrap();
+        zip[position] = b'B';
+        fs::write(&path, &zip).unwrap();
+        let report = check_format(&path).unwrap().unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("entry 'bad.txt': CRC32 mismatch"));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.txt");
+        fs::write(&path, b"plain text").unwrap();
+        assert!(check_format(&path).unwrap().is_none());
+    }
+}
@@ -0,0 +1,492 @@
+// Import standard library components
+use std::{
+    collections::{BTreeMap, HashMap, HashSet}, // Record lookup
+    env,                                       // Invocation details for the header
+    error::Error,                              // Error trait for boxed errors
+    fs,                                        // File system access
+    path::PathBuf,                             // Path manipulation
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::error::SlashsumError;
+use crate::hasher::{Algorithm, Checksums, hash_file, parse_algorithms};
+use crate::manifest::collect_inputs;
+use crate::parse_path;
+
+/// Algorithms shared by slashsum and hashdeep
+pub const SUPPORTED: [Algorithm; 3] = [Algorithm::Md5, Algorithm::Sha1, Algorithm::Sha256];
+
+/// Algorithms written by hashdeep when none are selected
+pub const DEFAULT: [Algorithm; 2] = [Algorithm::Md5, Algorithm::Sha256];
+
+/// First header line of every hashdeep file
+const HEADER: &str = "%%%% HASHDEEP-1.0";
+
+/// One line of a hashdeep file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub size: u64,
+    pub digests: Vec<String>, // In the order of the header columns
+    pub path: String,
+}
+
+impl Record {
+    /// Builds a record from computed checksums
+    pub fn new(path: &str, checksums: &Checksums, algorithms: &[Algorithm]) -> Record {
+        Record {
+            size: checksums.size,
+            digests: algorithms
+                .iter()
+                .map(|algo| checksums.get(*algo).to_string())
+                .collect(),
+            path: path.to_string(),
+        }
+    }
+}
+
+/// Parsed hashdeep file
+#[derive(Debug, PartialEq, Eq)]
+pub struct HashdeepFile {
+    pub algorithms: Vec<Algorithm>,
+    pub records: Vec<Record>,
+}
+
+/// Drops the leading "./" that `hashdeep -r .` writes, so paths compare
+/// equal to the relative paths slashsum lists
+fn normalize_path(path: &str) -> &str {
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest.trim_start_matches('/');
+    }
+    path
+}
+
+/// Checks that every algorithm can be written in a hashdeep file
+pub fn check_algorithms(algorithms: &[Algorithm]) -> Result<(), String> {
+    match algorithms.iter().find(|algo| !SUPPORTED.contains(algo)) {
+        Some(algo) => Err(format!(
+            "{} is not supported by the hashdeep format (use md5, sha1 or sha256)",
+            algo.name()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses a hashdeep file ("%%%% HASHDEEP-1.0" header, then size,hashes...,filename)
+/// Lines starting with '#' are comments; filenames may contain commas, and
+/// lose their leading "./"
+pub fn parse(content: &str) -> Result<HashdeepFile, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    if lines.next().map(|(_, line)| line.trim_end()) != Some(HEADER) {
+        return Err(format!("Missing '{}' header", HEADER));
+    }
+
+    // Column header: "%%%% size,md5,sha256,filename"
+    let (_, columns) = lines.next().ok_or("Missing column header")?;
+    let columns: Vec<&str> = columns
+        .trim_end()
+        .strip_prefix("%%%% ")
+        .ok_or("Invalid column header")?
+        .split(',')
+        .collect();
+    if columns.len() < 3 || columns[0] != "size" || columns[columns.len() - 1] != "filename" {
+        return Err("Invalid column header".to_string());
+    }
+    let algorithms = columns[1..columns.len() - 1]
+        .iter()
+        .map(|name| Algorithm::from_name(name).ok_or(format!("Unknown hash column '{}'", name)))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_algorithms(&algorithms)?;
+
+    let mut records = Vec::new();
+    for (number, line) in lines {
+        // Windows hashdeep writes CRLF line endings
+        let line = line.trim_end_matches('\r');
+        if line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("Line {}: invalid hashdeep record", number + 1);
+        let fields: Vec<&str> = line.splitn(algorithms.len() + 2, ',').collect();
+        if fields.len() != algorithms.len() + 2 {
+            return Err(invalid());
+        }
+        let size = fields[0].parse().map_err(|_| invalid())?;
+        let digests: Vec<String> = fields[1..=algorithms.len()]
+            .iter()
+            .map(|digest| digest.to_ascii_lowercase())
+            .collect();
+        if digests
+            .iter()
+            .zip(&algorithms)
+            .any(|(digest, algo)| digest.len() != algo.hex_len())
+        {
+            return Err(invalid());
+        }
+        records.push(Record {
+            size,
+            digests,
+            path: normalize_path(fields[algorithms.len() + 1]).to_string(),
+        });
+    }
+
+    Ok(HashdeepFile {
+        algorithms,
+        records,
+    })
+}
+
+/// Renders a hashdeep file, with records in the given order
+pub fn render(algorithms: &[Algorithm], records: &[&Record]) -> String {
+    let columns: Vec<String> = algorithms
+        .iter()
+        .map(|algo| algo.name().to_ascii_lowercase())
+        .collect();
+
+    let mut output = format!("{}\n%%%% size,{},filename\n", HEADER, columns.join(","));
+    if let Ok(dir) = env::current_dir() {
+        output.push_str(&format!("## Invoked from: {}\n", dir.display()));
+    }
+    let command: Vec<String> = env::args().collect();
+    output.push_str(&format!("## $ {}\n##\n", command.join(" ")));
+
+    for record in records {
+        output.push_str(&format!(
+            "{},{},{}\n",
+            record.size,
+            record.digests.join(","),
+            record.path
+        ));
+    }
+    output
+}
+
+/// Merges new entries into the records of an existing hashdeep file (if any)
+/// and renders the result sorted by path
+pub fn merge(
+    existing: Option<&str>,
+    algorithms: &[Algorithm],
+    entries: &[(String, Checksums)],
+) -> Result<String, String> {
+    let mut records: BTreeMap<String, Record> = BTreeMap::new();
+
+    if let Some(content) = existing.filter(|c| !c.trim().is_empty()) {
+        let file = parse(content)?;
+        if file.algorithms != algorithms {
+            return Err("Existing hashdeep file uses different hash columns".to_string());
+        }
+        for record in file.records {
+            records.insert(record.path.clone(), record);
+        }
+    }
+
+    for (path, checksums) in entries {
+        records.insert(path.clone(), Record::new(path, checksums, algorithms));
+    }
+
+    Ok(render(algorithms, &records.values().collect::<Vec<_>>()))
+}
+
+/// Classification of the files found during an audit
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub examined: usize,
+    pub expected: usize,
+    pub matched: Vec<String>,
+    pub moved: Vec<(String, String)>, // (known path, current path)
+    pub new: Vec<String>,
+    pub changed: Vec<String>,
+    pub missing: Vec<String>,
+    pub unreadable: Vec<String>, // Could not be hashed
+}
+
+impl AuditReport {
+    /// An audit passes when every file matched a known file at the same path
+    pub fn passed(&self) -> bool {
+        self.moved.is_empty()
+            && self.new.is_empty()
+            && self.changed.is_empty()
+            && self.missing.is_empty()
+            && self.unreadable.is_empty()
+    }
+}
+
+/// Compares hashed files with a known hashdeep file
+/// - matched: same path, same content
+/// - moved:   content of a known file found at another path
+/// - changed: known path, different content
+/// - new:     unknown path and unknown content
+/// - missing: known files not found by any of the above
+///
+/// Files that could not be hashed are only reported as unreadable
+pub fn audit(
+    known: &HashdeepFile,
+    scanned: &[(String, Checksums)],
+    unreadable: &[String],
+) -> AuditReport {
+    let by_path: HashMap<&str, &Record> = known
+        .records
+        .iter()
+        .map(|record| (record.path.as_str(), record))
+        .collect();
+    let mut by_content: HashMap<(u64, &[String]), Vec<&str>> = HashMap::new();
+    for record in &known.records {
+        by_content
+            .entry((record.size, &record.digests))
+            .or_default()
+            .push(&record.path);
+    }
+
+    let mut report = AuditReport {
+        examined: scanned.len(),
+        expected: known.records.len(),
+        ..AuditReport::default()
+    };
+    let mut used: HashSet<&str> = unreadable.iter().map(|path| normalize_path(path)).collect();
+    report.unreadable = unreadable
+        .iter()
+        .map(|path| normalize_path(path).to_string())
+        .collect();
+    let records: Vec<Record> = scanned
+        .iter()
+        .map(|(path, checksums)| Record::new(normalize_path(path), checksums, &known.algorithms))
+        .collect();
+    let same_content = |a: &Record, b: &Record| a.size == b.size && a.digests == b.digests;
+
+    // Exact matches first, so a copy elsewhere is never reported as a move of a file
+    // that is still in place
+    let mut unmatched = Vec::new();
+    for current in &records {
+        match by_path.get(current.path.as_str()) {
+            Some(record) if same_content(record, current) => {
+                used.insert(record.path.as_str());
+                report.matched.push(current.path.clone());
+            }
+            _ => unmatched.push(current),
+        }
+    }
+
+    for current in unmatched {
+        let key = (current.size, current.digests.as_slice());
+        if let Some(origin) = by_content
+            .get(&key)
+            .and_then(|paths| paths.iter().find(|p| !used.contains(*p)))
+        {
+            used.insert(origin);
+            report
+                .moved
+                .push((origin.to_string(), current.path.clone()));
+        } else if let Some(record) = by_path.get(current.path.as_str()) {
+            used.insert(record.path.as_str());
+            report.changed.push(current.path.clone());
+        } else {
+            report.new.push(current.path.clone());
+        }
+    }
+
+    report.missing = known
+        .records
+        .iter()
+        .filter(|record| !used.contains(record.path.as_str()))
+        .map(|record| record.path.clone())
+        .collect();
+    report
+}
+
+/// Options of the `audit` subcommand
+#[derive(Args, Debug)]
+pub struct AuditOptions {
+    /// hashdeep file listing the known digests
+    #[arg(short, long, value_name = "FILE", value_parser = parse_path)]
+    pub known: PathBuf,
+    /// Files or directories to audit
+    #[arg(value_name = "PATH", required = true, value_parser = parse_path)]
+    pub inputs: Vec<PathBuf>,
+    /// Compare paths relative to DIR
+    #[arg(long, value_name = "DIR", value_parser = parse_path)]
+    pub base: Option<PathBuf>,
+    /// Prepend PATH to every compared path
+    #[arg(long, value_name = "PATH")]
+    pub prefix: Option<String>,
+}
+
+/// Runs the `audit` subcommand and prints hashdeep-like results
+/// Returns true when the audit passed
+pub fn run(options: &AuditOptions) -> Result<bool, Box<dyn Error>> {
+    let content = fs::read_to_string(&options.known)
+        .map_err(|e| SlashsumError::io(format!("Cannot read '{}'", options.known.display()), &e))?;
+    let known = parse(&content).map_err(|e| format!("{}: {}", options.known.display(), e))?;
+
+    let files = collect_inputs(
+        &options.inputs,
+        options.base.as_deref(),
+        options.prefix.as_deref(),
+        std::slice::from_ref(&options.known),
+    )?;
+    let mut scanned = Vec::with_capacity(files.len());
+    let mut unreadable = Vec::new();
+    for (path, file) in files {
+        match hash_file(&file) {
+            Ok(checksums) => scanned.push((path, checksums)),
+            Err(e) => {
+                eprintln!("Warning: {}: {}", file.display(), e);
+                unreadable.push(path);
+            }
+        }
+    }
+
+    let report = audit(&known, &scanned, &unreadable);
+    for (from, to) in &report.moved {
+        println!("Moved: {} -> {}", from, to);
+    }
+    for path in &report.changed {
+        println!("Changed: {}", path);
+    }
+    for path in &report.new {
+        println!("New: {}", path);
+    }
+    for path in &report.missing {
+        println!("Missing: {}", path);
+    }
+    for path in &report.unreadable {
+        println!("Unreadable: {}", path);
+    }
+
+    let status = if report.passed() { "passed" } else { "failed" };
+    println!("slashsum: Audit {}", status);
+    println!("       Input files examined: {}", report.examined);
+    println!("      Known files expecting: {}", report.expected);
+    println!("              Files matched: {}", report.matched.len());
+    println!("                Files moved: {}", report.moved.len());
+    println!("            New files found: {}", report.new.len());
+    println!("              Files changed: {}", report.changed.len());
+    println!("      Known files not found: {}", report.missing.len());
+    println!("           Files unreadable: {}", report.unreadable.len());
+
+    Ok(report.passed())
+}
+
+/// Parses --algo for hashdeep output, defaulting to hashdeep's md5,sha256
+pub fn parse_hashdeep_algorithms(list: Option<&str>) -> Result<Vec<Algorithm>, String> {
+    let algorithms = match list {
+        Some(list) => parse_algorithms(list)?,
+        None => DEFAULT.to_vec(),
+    };
+    check_algorithms(&algorithms)?;
+    Ok(algorithms)
+}
+
+/// Configured algorithms the hashdeep format supports, or the hashdeep
+/// defaults when it supports none of them
+pub fn configured_algorithms(list: &str) -> Vec<Algorithm> {
+    let algorithms: Vec<Algorithm> = parse_algorithms(list)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|algo| SUPPORTED.contains(algo))
+        .collect();
+    if algorithms.is_empty() {
+        DEFAULT.to_vec()
+    } else {
+        algorithms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::hash_reader;
+
+    const KNOWN: &str = "%%%% HASHDEEP-1.0\n\
+        %%%% size,md5,sha256,filename\n\
+        ## Invoked from: /data\n\
+        ## $ hashdeep -r .\n\
+        ##\n\
+        3,900150983cd24fb0d6963f7d28e17f72,ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad,./abc.txt\n\
+        0,d41d8cd98f00b204e9800998ecf8427e,e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855,./dir/empty, with comma\n";
+
+    #[test]
+    fn test_parse() {
+        let file = parse(KNOWN).unwrap();
+        assert_eq!(file.algorithms, vec![Algorithm::Md5, Algorithm::Sha256]);
+        assert_eq!(file.records.len(), 2);
+        assert_eq!(file.records[0].size, 3);
+        // hashdeep's "./" is dropped, as in the paths slashsum lists
+        assert_eq!(file.records[0].path, "abc.txt");
+        assert_eq!(file.records[1].path, "dir/empty, with comma");
+
+        // CRLF line endings, as written on Windows, even without a final newline
+        let crlf = KNOWN.replace('\n', "\r\n");
+        assert_eq!(parse(crlf.trim_end_matches('\n')).unwrap(), file);
+
+        assert!(parse("3,abc,./file\n").is_err());
+        assert!(parse("%%%% HASHDEEP-1.0\n%%%% size,crc32,filename\n").is_err());
+        assert!(parse("%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n3,abc,./x\n").is_err());
+    }
+
+    #[test]
+    fn test_merge_roundtrip() {
+        let entries = vec![
+            ("./b".to_string(), hash_reader(&b"b"[..]).unwrap()),
+            ("./a".to_string(), hash_reader(&b"abc"[..]).unwrap()),
+        ];
+        let content = merge(None, &DEFAULT, &entries).unwrap();
+        let file = parse(&content).unwrap();
+        assert_eq!(file.records[0].path, "a");
+        assert_eq!(
+            file.records[0].digests[0],
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(file.records[1].path, "b");
+
+        // Paths with and without "./" are the same file
+        let update = vec![("a".to_string(), hash_reader(&b"new"[..]).unwrap())];
+        let file = parse(&merge(Some(&content), &DEFAULT, &update).unwrap()).unwrap();
+        assert_eq!(file.records.len(), 2);
+        assert_eq!(file.records[0].size, 3);
+
+        // Merging with different columns is refused
+        assert!(merge(Some(&content), &[Algorithm::Sha1], &entries).is_err());
+    }
+
+    #[test]
+    fn test_audit() {
+        let known = parse(KNOWN).unwrap();
+        // Paths as listed by slashsum, without hashdeep's "./"
+        let scanned = vec![
+            ("moved.txt".to_string(), hash_reader(&b"abc"[..]).unwrap()),
+            (
+                "dir/empty, with comma".to_string(),
+                hash_reader(&b"x"[..]).unwrap(),
+            ),
+            ("new.txt".to_string(), hash_reader(&b"new"[..]).unwrap()),
+        ];
+
+        let report = audit(&known, &scanned, &[]);
+        assert_eq!(
+            report.moved,
+            vec![("abc.txt".to_string(), "moved.txt".to_string())]
+        );
+        assert_eq!(report.changed, vec!["dir/empty, with comma"]);
+        assert_eq!(report.new, vec!["new.txt"]);
+        assert!(report.missing.is_empty());
+        assert!(!report.passed());
+
+        // Same with a "./" prefix
+        let scanned = vec![("./abc.txt".to_string(), hash_reader(&b"abc"[..]).unwrap())];
+        let report = audit(&known, &scanned, &[]);
+        assert_eq!(report.matched, vec!["abc.txt"]);
+        assert_eq!(report.missing, vec!["dir/empty, with comma"]);
+
+        // An unreadable known file is not missing, but fails the audit
+        let unreadable = vec!["./dir/empty, with comma".to_string()];
+        let report = audit(&known, &scanned, &unreadable);
+        assert!(report.missing.is_empty());
+        assert_eq!(report.unreadable, vec!["dir/empty, with comma"]);
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,1035 @@
+// Import standard library components
+use std::{
+    error::Error,                                // Error trait for boxed errors
+    fs::{self, File},                            // File handling
+    io::{self, BufReader, Read, Seek, SeekFrom}, // Reading from any byte source
+    path::Path,                                  // Path manipulation
+    sync::Arc, // Atomic Reference Counted pointer for thread-safe sharing
+    sync::atomic::{AtomicBool, Ordering}, // Interruption flag
+    thread,    // Thread management
+    time::SystemTime, // Modification times
+};
+
+use crate::interrupt::{self, Interrupted};
+
+// External crates
+use crc::Crc; // CRC32 implementation
+use crossbeam_channel::bounded; // Thread communication channels
+use md5::Md5; // MD5 hasher
+use sha1::{Digest, Sha1}; // SHA1 hasher
+use sha2::digest::common::hazmat::{SerializableState, SerializedState}; // Checkpoints
+use sha2::{Sha256, Sha512}; // SHA256 and SHA512 hashers
+
+/// Size of the chunks sent to the hasher threads (1MB)
+pub const CHUNK_SIZE: usize = 1_048_576;
+
+/// Checksum algorithms computed by slashsum, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Algorithm {
+    Crc32,
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    /// All algorithms, in display order
+    pub const ALL: [Algorithm; 5] = [
+        Algorithm::Crc32,
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Sha256,
+        Algorithm::Sha512,
+    ];
+
+    /// Upper-case name used in output and manifests (e.g. "SHA256")
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "CRC32",
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha512 => "SHA512",
+        }
+    }
+
+    /// Length of the hexadecimal digest
+    pub fn hex_len(self) -> usize {
+        match self {
+            Algorithm::Crc32 => 8,
+            Algorithm::Md5 => 32,
+            Algorithm::Sha1 => 40,
+            Algorithm::Sha256 => 64,
+            Algorithm::Sha512 => 128,
+        }
+    }
+
+    /// Algorithms whose digests have the same length as a hexadecimal string
+    pub fn candidates(hex: &str) -> Vec<Algorithm> {
+        Algorithm::ALL
+            .into_iter()
+            .filter(|algo| algo.hex_len() == hex.len())
+            .collect()
+    }
+
+    /// Parses an algorithm name, ignoring case and dashes ("sha-256" → SHA256)
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        let normalized = name.replace('-', "").to_ascii_uppercase();
+        Algorithm::ALL
+            .into_iter()
+            .find(|algo| algo.name() == normalized)
+    }
+}
+
+/// Parses a comma-separated list of algorithm names ("sha256,md5" or "all")
+pub fn parse_algorithms(list: &str) -> Result<Vec<Algorithm>, String> {
+    if list.eq_ignore_ascii_case("all") {
+        return Ok(Algorithm::ALL.to_vec());
+    }
+
+    let mut algorithms = Vec::new();
+    for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let algo = Algorithm::from_name(name).ok_or(format!("Unknown algorithm '{}'", name))?;
+        if !algorithms.contains(&algo) {
+            algorithms.push(algo);
+        }
+    }
+
+    if algorithms.is_empty() {
+        return Err("No algorithm specified".to_string());
+    }
+    algorithms.sort();
+    Ok(algorithms)
+}
+
+/// Validates a hexadecimal digest and normalizes it to lower case
+/// The digest length must match at least one supported algorithm
+pub fn parse_digest(value: &str) -> Result<String, String> {
+    let digest = value.trim().to_ascii_lowercase();
+    if digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a hexadecimal digest", value));
+    }
+    if Algorithm::candidates(&digest).is_empty() {
+        return Err(format!(
+            "No supported algorithm produces {}-character digests",
+            digest.len()
+        ));
+    }
+    Ok(digest)
+}
+
+/// Results of hashing one input with every algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksums {
+    pub size: u64,
+    pub crc32: String,
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+    pub sha512: String,
+}
+
+impl Checksums {
+    /// Returns the hexadecimal digest for one algorithm
+    pub fn get(&self, algo: Algorithm) -> &str {
+        match algo {
+            Algorithm::Crc32 => &self.crc32,
+            Algorithm::Md5 => &self.md5,
+            Algorithm::Sha1 => &self.sha1,
+            Algorithm::Sha256 => &self.sha256,
+            Algorithm::Sha512 => &self.sha512,
+        }
+    }
+
+    /// Finds the algorithm whose digest equals an expected hexadecimal value
+    /// Every algorithm with a matching digest length is checked, case-insensitively
+    pub fn find_match(&self, expected: &str) -> Option<Algorithm> {
+        Algorithm::candidates(expected)
+            .into_iter()
+            .find(|algo| self.get(*algo).eq_ignore_ascii_case(expected))
+    }
+}
+
+/// Computes a hash by processing data chunks on the fly
+/// Parameters:
+/// - rx: Channel receiver for data chunks
+/// - stop: Interruption flag; once set, the remaining chunks are received but
+///   discarded, so the sender never fails and the thread can be joined quickly
+/// - initializer: Function that initializes the hash context
+/// - updater: Function that updates the context with new data
+/// - finalizer: Function that produces the final hash
+pub fn compute_hash<H, C, I, U, F>(
+    rx: crossbeam_channel::Receiver<Arc<[u8]>>,
+    stop: &AtomicBool,
+    initializer: I,
+    updater: U,
+    finalizer: F,
+) -> H
+where
+    I: FnOnce() -> C,
+    U: Fn(&mut C, &[u8]),
+    F: FnOnce(C) -> H,
+{
+    // Initialize the hash context
+    let mut context = initializer();
+
+    // Process each data chunk as it is received
+    while let Ok(chunk) = rx.recv() {
+        if !stop.load(Ordering::Relaxed) {
+            updater(&mut context, &chunk);
+        }
+    }
+
+    // Finalize the hash
+    finalizer(context)
+}
+
+/// CRC32 (ISO-HDLC, as in zip and gzip) shared by every calculator
+static CRC32: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+// Structure to encapsulate CRC32 calculation
+#[derive(Clone)]
+pub struct Crc32Calculator {
+    digest: crc::Digest<'static, u32>,
+}
+
+impl Crc32Calculator {
+    pub fn new() -> Self {
+        Self {
+            digest: CRC32.digest(),
+        }
+    }
+
+    /// Continues a calculation from the CRC32 of the data processed so far
+    pub fn resume(crc: u32) -> Self {
+        // The register holds the reflected CRC before the final XOR
+        let register = crc ^ CRC32.algorithm.xorout;
+        Self {
+            digest: CRC32.digest_with_initial(register.reverse_bits()),
+        }
+    }
+
+    pub fn update(&mut self, new_data: &[u8]) {
+        self.digest.update(new_data);
+    }
+
+    pub fn finalize(self) -> u32 {
+        self.digest.finalize()
+    }
+}
+
+/// Formats bytes as lowercase hexadecimal
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parses hexadecimal into bytes; None if the text is not valid hexadecimal
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Running state of every algorithm; it can be saved and restored, so that
+/// hashing can stop and resume at any chunk boundary (--checkpoint)
+#[derive(Clone)]
+pub struct HashState {
+    crc32: Crc32Calculator,
+    md5: Md5,
+    sha1: Sha1,
+    sha256: Sha256,
+    sha512: Sha512,
+}
+
+impl HashState {
+    pub fn new() -> Self {
+        HashState {
+            crc32: Crc32Calculator::new(),
+            md5: Md5::new(),
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+            sha512: Sha512::new(),
+        }
+    }
+
+    /// Produces the checksums of the `size` bytes processed
+    pub fn finish(self, size: u64) -> Checksums {
+        Checksums {
+            size,
+            crc32: format!("{:08x}", self.crc32.finalize()),
+            md5: to_hex(&self.md5.finalize()),
+            sha1: to_hex(&self.sha1.finalize()),
+            sha256: to_hex(&self.sha256.finalize()),
+            sha512: to_hex(&self.sha512.finalize()),
+        }
+    }
+
+    /// Internal state of each algorithm, as hexadecimal
+    /// CRC32 is saved as the checksum so far, from which it can resume
+    pub fn export(&self) -> Vec<(Algorithm, String)> {
+        vec![
+            (
+                Algorithm::Crc32,
+                format!("{:08x}", self.crc32.clone().finalize()),
+            ),
+            (Algorithm::Md5, to_hex(&self.md5.serialize())),
+            (Algorithm::Sha1, to_hex(&self.sha1.serialize())),
+            (Algorithm::Sha256, to_hex(&self.sha256.serialize())),
+            (Algorithm::Sha512, to_hex(&self.sha512.serialize())),
+        ]
+    }
+
+    /// Restores a state saved by `export`
+    pub fn import(fields: &[(Algorithm, String)]) -> Result<HashState, String> {
+        let field = |algo: Algorithm| {
+            fields
+                .iter()
+                .find(|(name, _)| *name == algo)
+                .map(|(_, value)| value.as_str())
+                .ok_or(format!("Missing {} state", algo.name()))
+        };
+        let invalid = |algo: Algorithm| format!("Invalid {} state", algo.name());
+
+        let crc32 = u32::from_str_radix(field(Algorithm::Crc32)?, 16)
+            .map_err(|_| invalid(Algorithm::Crc32))?;
+        Ok(HashState {
+            crc32: Crc32Calculator::resume(crc32),
+            md5: restore(field(Algorithm::Md5)?).ok_or(invalid(Algorithm::Md5))?,
+            sha1: restore(field(Algorithm::Sha1)?).ok_or(invalid(Algorithm::Sha1))?,
+            sha256: restore(field(Algorithm::Sha256)?).ok_or(invalid(Algorithm::Sha256))?,
+            sha512: restore(field(Algorithm::Sha512)?).ok_or(invalid(Algorithm::Sha512))?,
+        })
+    }
+}
+
+/// Deserializes the internal state of a hasher from hexadecimal
+fn restore<D: SerializableState>(text: &str) -> Option<D> {
+    let bytes = from_hex(text)?;
+    let state = <&SerializedState<D>>::try_from(bytes.as_slice()).ok()?;
+    D::deserialize(state).ok()
+}
+
+/// Reads a source to the end and computes all checksums in parallel
+/// Each algorithm runs in its own thread and receives the same 1MB chunks
+pub fn hash_reader<R: Read>(reader: R) -> Result<Checksums, Box<dyn Error>> {
+    hash_reader_until(reader, interrupt::flag())
+}
+
+/// Same as `hash_reader`, stopping early once `stop` is set
+fn hash_reader_until<R: Read>(
+    mut reader: R,
+    stop: &'static AtomicBool,
+) -> Result<Checksums, Box<dyn Error>> {
+    let (state, size) = hash_segment(&mut reader, HashState::new(), None, stop)?;
+    Ok(state.finish(size))
+}
+
+/// Feeds up to `limit` bytes of a source (or all of it) into a running state
+/// and returns the updated state with the number of bytes read; fewer bytes
+/// than `limit` means the end of the source was reached
+/// Once `stop` is set, the hasher threads still drain the chunks already sent
+/// before being joined; the result is then an `Interrupted` error
+pub fn hash_segment<R: Read>(
+    reader: &mut R,
+    state: HashState,
+    limit: Option<u64>,
+    stop: &'static AtomicBool,
+) -> Result<(HashState, u64), Box<dyn Error>> {
+    let HashState {
+        crc32,
+        md5,
+        sha1,
+        sha256,
+        sha512,
+    } = state;
+
+    // Create communication channels for each hash algorithm
+    let (crc32_tx, crc32_rx) = bounded(1024); // CRC32 channel
+    let (md5_tx, md5_rx) = bounded(1024); // MD5 channel
+    let (sha1_tx, sha1_rx) = bounded(1024); // SHA1 channel
+    let (sha256_tx, sha256_rx) = bounded(1024); // SHA256 channel
+    let (sha512_tx, sha512_rx) = bounded(1024); // SHA512 channel
+
+    // Spawn thread for CRC32 calculation
+    let crc32_handle = thread::spawn(move || {
+        compute_hash(
+            crc32_rx,
+            stop,
+            || crc32,
+            |calculator, data| calculator.update(data),
+            |calculator| calculator,
+        )
+    });
+
+    // Spawn thread for MD5 calculation
+    let md5_handle = thread::spawn(move || {
+        compute_hash(
+            md5_rx,
+            stop,
+            || md5,                               // continue the MD5 context
+            |context, data| context.update(data), // update with data
+            |context| context,                    // keep the running state
+        )
+    });
+
+    // Spawn thread for SHA1 calculation
+    let sha1_handle = thread::spawn(move || {
+        compute_hash(
+            sha1_rx,
+            stop,
+            || sha1, // continue the SHA1 context
+            |digest, data| digest.update(data),
+            |digest| digest,
+        )
+    });
+
+    // Spawn thread for SHA256 calculation
+    let sha256_handle = thread::spawn(move || {
+        compute_hash(
+            sha256_rx,
+            stop,
+            || sha256, // continue the SHA256 context
+            |digest, data| digest.update(data),
+            |digest| digest,
+        )
+    });
+
+    // Spawn thread for SHA512 calculation
+    let sha512_handle = thread::spawn(move || {
+        compute_hash(
+            sha512_rx,
+            stop,
+            || sha512, // continue the SHA512 context
+            |digest, data| digest.update(data),
+            |digest| digest,
+        )
+    });
+
+    // Read input in 1MB chunks
+    let mut size: u64 = 0;
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let wanted = match limit {
+            Some(limit) => (limit - size).min(CHUNK_SIZE as u64) as usize,
+            None => CHUNK_SIZE,
+        };
+        if wanted == 0 {
+            break;
+        }
+        let mut buffer = vec![0; wanted]; // 1MB buffer
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            // End of file
+            break;
+        }
+        size += bytes_read as u64;
+        buffer.truncate(bytes_read);
+        let chunk = Arc::from(buffer.into_boxed_slice());
+
+        // Send chunk to all hash channels
+        crc32_tx.send(Arc::clone(&chunk))?;
+        md5_tx.send(Arc::clone(&chunk))?;
+        sha1_tx.send(Arc::clone(&chunk))?;
+        sha256_tx.send(Arc::clone(&chunk))?;
+        sha512_tx.send(chunk)?; // Final send uses original Arc
+    }
+
+    // Close all transmission channels
+    drop(crc32_tx);
+    drop(md5_tx);
+    drop(sha1_tx);
+    drop(sha256_tx);
+    drop(sha512_tx);
+
+    // Collect results from all threads
+    let state = HashState {
+        crc32: crc32_handle.join().map_err(|_| "Thread CRC32 error")?,
+        md5: md5_handle.join().map_err(|_| "Thread MD5 error")?,
+        sha1: sha1_handle.join().map_err(|_| "Thread SHA1 error")?,
+        sha256: sha256_handle.join().map_err(|_| "Thread SHA256 error")?,
+        sha512: sha512_handle.join().map_err(|_| "Thread SHA512 error")?,
+    };
+    // Also when the flag was set after the last read: the hashers then skipped
+    // the chunks still queued, and the state is incomplete
+    if stop.load(Ordering::SeqCst) {
+        return Err(Interrupted { bytes: size }.into());
+    }
+    Ok((state, size))
+}
+
+/// Size and timestamps of a file, compared before and after hashing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileState {
+    size: u64,
+    mtime: Option<SystemTime>,
+    ctime: Option<(i64, i64)>, // Seconds and nanoseconds (Unix only)
+}
+
+impl FileState {
+    /// Reads the state of a regular file; None for devices, FIFOs and directories,
+    /// whose timestamps change as they are used
+    pub fn of(path: &Path) -> io::Result<Option<FileState>> {
+        let metadata = fs::metadata(path)?;
+        if !metadata.is_file() {
+            return Ok(None);
+        }
+        #[cfg(unix)]
+        let ctime = {
+            use std::os::unix::fs::MetadataExt;
+            Some((metadata.ctime(), metadata.ctime_nsec()))
+        };
+        #[cfg(not(unix))]
+        let ctime = None;
+        Ok(Some(FileState {
+            size: metadata.len(),
+            mtime: metadata.modified().ok(),
+            ctime,
+        }))
+    }
+}
+
+/// Opens a file and computes all its checksums through a 1MB buffered reader
+pub fn hash_file(path: &Path) -> Result<Checksums, Box<dyn Error>> {
+    let file = File::open(path)?;
+    hash_reader(BufReader::with_capacity(CHUNK_SIZE, file))
+}
+
+/// Describes special files (devices, FIFOs, sockets), whose metadata size is
+/// meaningless; None for regular files and directories
+pub fn special_kind(metadata: &fs::Metadata) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let file_type = metadata.file_type();
+        if file_type.is_block_device() {
+            return Some("block device");
+        } else if file_type.is_char_device() {
+            return Some("character device");
+        } else if file_type.is_fifo() {
+            return Some("FIFO");
+        } else if file_type.is_socket() {
+            return Some("socket");
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    None
+}
+
+/// Size of a block device, queried from the kernel
+#[cfg(target_os = "linux")]
+fn block_device_size(file: &File) -> io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+    const BLKGETSIZE64: libc::c_ulong = 0x8008_1272; // _IOR(0x12, 114, u64)
+    let mut size: u64 = 0;
+    // SAFETY: BLKGETSIZE64 writes a single u64 through the pointer
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64 as _, &mut size) };
+    if result == 0 {
+        Ok(size)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Size of a block device, found by seeking to its end
+#[cfg(not(target_os = "linux"))]
+fn block_device_size(mut file: &File) -> io::Result<u64> {
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(size)
+}
+
+/// Number of bytes a file will provide, when it is known before reading it
+/// Empty regular files may be pseudo-files (e.g. in /proc) whose content is
+/// generated when read, so their size is unknown as well
+pub fn expected_size(file: &File) -> io::Result<Option<u64>> {
+    let metadata = file.metadata()?;
+    if metadata.is_file() {
+        Ok((metadata.len() > 0).then_some(metadata.len()))
+    } else if special_kind(&metadata) == Some("block device") {
+        block_device_size(file).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Hashes part of a file: `length` bytes starting at `offset`, or up to the end
+/// The returned size is the number of bytes actually hashed
+pub fn hash_file_range(
+    path: &Path,
+    offset: u64,
+    length: Option<u64>,
+) -> Result<Checksums, Box<dyn Error>> {
+    let mut file = File::open(path)?;
+    let beyond_end = |size: u64| {
+        format!(
+            "Offset {} is beyond the end of '{}' ({} bytes)",
+            offset,
+            path.display(),
+            size
+        )
+    };
+    if let Some(size) = expected_size(&file)?
+        && offset > size
+    {
+        return Err(beyond_end(size).into());
+    }
+
+    // Character devices, FIFOs and sockets cannot seek: skip bytes by reading them
+    let metadata = file.metadata()?;
+    if metadata.is_file() || special_kind(&metadata) == Some("block device") {
+        file.seek(SeekFrom::Start(offset))?;
+    } else {
+        let skipped = io::copy(&mut (&mut file).take(offset), &mut io::sink())?;
+        if skipped < offset {
+            return Err(beyond_end(skipped).into());
+        }
+    }
+
+    let reader = BufReader::with_capacity(CHUNK_SIZE, file);
+    match length {
+        Some(length) => hash_reader(reader.take(length)),
+        None => hash_reader(reader),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::bounded;
+    use md5::Md5;
+
+    use sha1::{Digest, Sha1};
+    use sha2::{Sha256, Sha512};
+
+    use std::time::Instant;
+
+    #[test]
+    fn test_compute_hash_md5() {
+        // Test MD5 with known "abc" input
+        let (tx, rx) = bounded(2);
+
+        tx.send(Arc::from([0x61u8, 0x62, 0x63])).unwrap(); // "abc"
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Md5::new,                              // initialize MD5 context
+            |context, data| context.update(data),  // update with data
+            |context| to_hex(&context.finalize()), // finalize and format
+        );
+
+        assert_eq!(result, "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    #[test]
+    fn test_compute_hash_crc32() {
+        // Test CRC32 with "Hello world!" input
+        let (tx, rx) = bounded(2);
+
+        tx.send(Arc::from(b"Hello world!".as_ref())).unwrap();
+        drop(tx);
+
+        // Use the same structure adopted for CRC32
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            || {
+                // Structure for CRC32 calculation
+                struct Crc32Calculator {
+                    crc_algo: Crc<u32>,
+                    data: Vec<u8>,
+                }
+
+                Crc32Calculator {
+                    crc_algo: Crc::<u32>::new(&crc::CRC_32_ISO_HDLC),
+                    data: Vec::new(),
+                }
+            },
+            |calculator, data| {
+                calculator.data.extend_from_slice(data);
+            },
+            |calculator| format!("{:08x}", calculator.crc_algo.checksum(&calculator.data)),
+        );
+
+        assert_eq!(result, "1b851995");
+    }
+
+    #[test]
+    fn test_compute_hash_sha1() {
+        // Test SHA1 with "abc"
+        let (tx, rx) = bounded(2);
+        tx.send(Arc::from(b"abc".as_ref())).unwrap();
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Sha1::new,
+            |digest, data| {
+                digest.update(data);
+            },
+            |digest| to_hex(&digest.finalize()),
+        );
+
+        assert_eq!(result, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_compute_hash_sha256() {
+        // Test SHA256 with "abc"
+        let (tx, rx) = bounded(2);
+        tx.send(Arc::from(b"abc".as_ref())).unwrap();
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Sha256::new,
+            |digest, data| {
+                digest.update(data);
+            },
+            |digest| to_hex(&digest.finalize()),
+        );
+
+        assert_eq!(
+            result,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_compute_hash_sha512() {
+        // Test SHA512 with "abc"
+        let (tx, rx) = bounded(2);
+        tx.send(Arc::from(b"abc".as_ref())).unwrap();
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Sha512::new,
+            |digest, data| {
+                digest.update(data);
+            },
+            |digest| to_hex(&digest.finalize()),
+        );
+
+        assert_eq!(
+            result,
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+    }
+
+    #[test]
+    fn test_compute_hash_empty_input() {
+        // Test with empty input
+        let (tx, rx) = bounded(1);
+        drop(tx); // Close channel immediately
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Md5::new,
+            |context, data| context.update(data),
+            |context| to_hex(&context.finalize()),
+        );
+
+        // MD5 of empty string
+        assert_eq!(result, "d41d8cd98f00b204e9800998ecf8427e");
+    }
+
+    #[test]
+    fn test_compute_hash_multiple_chunks() {
+        // Test with multiple chunks
+        let (tx, rx) = bounded(5);
+
+        // Send "Hello" then " World!" to form "Hello World!"
+        tx.send(Arc::from(b"Hello".as_ref())).unwrap();
+        tx.send(Arc::from(b" World!".as_ref())).unwrap();
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Md5::new,
+            |context, data| context.update(data),
+            |context| to_hex(&context.finalize()),
+        );
+
+        // MD5 of "Hello World!"
+        assert_eq!(result, "ed076287532e86365e841e92bfc50d8c");
+    }
+
+    #[test]
+    fn test_crc32_calculator() {
+        // Direct test of Crc32Calculator structure
+        let mut calculator = Crc32Calculator::new();
+        calculator.update(b"Hello");
+        calculator.update(b" World!");
+        let result = calculator.finalize();
+
+        // CRC32 of "Hello World!" - corrected value
+        assert_eq!(result, 472456355); // 0x1c291ca3 in decimal
+    }
+
+    #[test]
+    fn test_crc32_calculator_empty() {
+        // Test CRC32 with empty data
+        let calculator = Crc32Calculator::new();
+        let result = calculator.finalize();
+
+        // CRC32 of empty string
+        assert_eq!(result, 0x00000000);
+    }
+
+    #[test]
+    fn test_large_chunk_processing() {
+        // Test with large chunk (similar to 1MB buffer size)
+        let large_data = vec![0x42u8; 1_048_576]; // 1MB of data
+        let (tx, rx) = bounded(2);
+
+        tx.send(Arc::from(large_data.into_boxed_slice())).unwrap();
+        drop(tx);
+
+        let result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Md5::new,
+            |context, data| context.update(data),
+            |context| to_hex(&context.finalize()),
+        );
+
+        // This test verifies that large chunk processing works
+        assert_eq!(result.len(), 32); // MD5 always produces 32 hex characters
+    }
+
+    #[test]
+    fn test_channel_capacity() {
+        // Test with limited channel capacity
+        let (tx, rx) = bounded(1); // Very small capacity
+
+        // Use a thread to read data while sending
+        let handle = std::thread::spawn(move || {
+            compute_hash(
+                rx,
+                &AtomicBool::new(false),
+                Md5::new,
+                |context, data| context.update(data),
+                |context| to_hex(&context.finalize()),
+            )
+        });
+
+        // Send multiple chunks quickly
+        for i in 0..10 {
+            let data = format!("chunk{}", i);
+            tx.send(Arc::from(data.as_bytes())).unwrap();
+        }
+        drop(tx); // Close the channel
+
+        // Wait for result
+        let result = handle.join().unwrap();
+
+        // Verify all chunks were processed
+        assert_eq!(result.len(), 32);
+    }
+
+    #[test]
+    fn test_concurrent_hash_computation() {
+        // Test simulating real concurrent behavior
+        use std::thread;
+
+        let test_data = b"This is a test for concurrent hash computation";
+        let chunk = Arc::from(test_data.as_ref());
+
+        // Create multiple channels like in the main code
+        let (md5_tx, md5_rx) = bounded(1024);
+        let (sha1_tx, sha1_rx) = bounded(1024);
+
+        // Launch computation threads
+        let md5_handle = thread::spawn(move || {
+            compute_hash(
+                md5_rx,
+                &AtomicBool::new(false),
+                Md5::new,
+                |context, data| context.update(data),
+                |context| to_hex(&context.finalize()),
+            )
+        });
+
+        let sha1_handle = thread::spawn(move || {
+            compute_hash(
+                sha1_rx,
+                &AtomicBool::new(false),
+                Sha1::new,
+                |digest, data| {
+                    digest.update(data);
+                },
+                |digest| to_hex(&digest.finalize()),
+            )
+        });
+
+        // Send data
+        md5_tx.send(Arc::clone(&chunk)).unwrap();
+        sha1_tx.send(chunk).unwrap();
+
+        // Close channels
+        drop(md5_tx);
+        drop(sha1_tx);
+
+        // Retrieve results
+        let md5_result = md5_handle.join().unwrap();
+        let sha1_result = sha1_handle.join().unwrap();
+
+        // Verify results - corrected with actual computed value
+        assert_eq!(md5_result, "f801c3cb79c641ab70efc5b525af573c");
+        assert_eq!(sha1_result.len(), 40); // SHA1 produces 40 hex characters
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        // Full pipeline on an in-memory source
+        let result = hash_reader(&b"abc"[..]).unwrap();
+
+        assert_eq!(result.size, 3);
+        assert_eq!(result.crc32, "352441c2");
+        assert_eq!(result.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(result.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(result.get(Algorithm::Sha1), result.sha1);
+    }
+
+    #[test]
+    fn test_hash_reader_stopped() {
+        // Sets the flag on its first read, like a signal arriving mid-chunk
+        struct Signalled<'a> {
+            data: &'a [u8],
+            stop: &'a AtomicBool,
+        }
+        impl Read for Signalled<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.stop.store(true, Ordering::SeqCst);
+                self.data.read(buf)
+            }
+        }
+
+        static STOP: AtomicBool = AtomicBool::new(false);
+        let data = vec![0u8; 3 * CHUNK_SIZE];
+        let reader = Signalled {
+            data: &data,
+            stop: &STOP,
+        };
+        let error = hash_reader_until(reader, &STOP).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Interrupted>(),
+            Some(&Interrupted {
+                bytes: CHUNK_SIZE as u64
+            })
+        );
+    }
+
+    #[test]
+    fn test_hash_file_range() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"xxabcxx").unwrap();
+
+        let range = hash_file_range(file.path(), 2, Some(3)).unwrap();
+        assert_eq!(range, hash_reader(&b"abc"[..]).unwrap());
+
+        // Without a length, or with one past the end, hashing stops at the end
+        assert_eq!(hash_file_range(file.path(), 5, None).unwrap().size, 2);
+        assert_eq!(hash_file_range(file.path(), 5, Some(100)).unwrap().size, 2);
+        assert!(hash_file_range(file.path(), 8, None).is_err());
+    }
+
+    #[test]
+    fn test_file_state() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), b"abc").unwrap();
+        let before = FileState::of(file.path()).unwrap();
+        assert!(before.is_some());
+        assert_eq!(FileState::of(file.path()).unwrap(), before);
+
+        fs::write(file.path(), b"abcd").unwrap();
+        assert_ne!(FileState::of(file.path()).unwrap(), before);
+
+        // Directories and devices are not tracked
+        assert_eq!(FileState::of(Path::new(".")).unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_special_files() {
+        // /dev/zero reports a size of 0 but provides as many bytes as requested
+        let path = Path::new("/dev/zero");
+        let metadata = fs::metadata(path).unwrap();
+        assert_eq!(special_kind(&metadata), Some("character device"));
+        assert_eq!(expected_size(&File::open(path).unwrap()).unwrap(), None);
+
+        let checksums = hash_file_range(path, 10, Some(3)).unwrap();
+        assert_eq!(checksums, hash_reader(&[0u8; 3][..]).unwrap());
+    }
+
+    #[test]
+    fn test_find_match() {
+        let result = hash_reader(&b"abc"[..]).unwrap();
+
+        assert_eq!(
+            result.find_match("A9993E364706816ABA3E25717850C26C9CD0D89D"),
+            Some(Algorithm::Sha1)
+        );
+        assert_eq!(result.find_match("352441c2"), Some(Algorithm::Crc32));
+        assert_eq!(result.find_match("00000000"), None);
+        assert_eq!(result.find_match("abcd"), None);
+        assert_eq!(
+            Algorithm::candidates(&"0".repeat(64)),
+            vec![Algorithm::Sha256]
+        );
+    }
+
+    #[test]
+    fn test_parse_algorithms() {
+        // Names are case-insensitive, deduplicated and sorted in display order
+        assert_eq!(
+            parse_algorithms("sha-256,MD5,sha256").unwrap(),
+            vec![Algorithm::Md5, Algorithm::Sha256]
+        );
+        assert_eq!(parse_algorithms("all").unwrap(), Algorithm::ALL.to_vec());
+        assert!(parse_algorithms("whirlpool").is_err());
+        assert!(parse_algorithms("").is_err());
+    }
+
+    #[test]
+    #[ignore] // Ignored by default, run with cargo test -- --ignored
+    fn benchmark_hash_computation() {
+        let large_data = vec![0x42u8; 10_000_000]; // 10MB of data
+        let chunk = Arc::from(large_data.into_boxed_slice());
+
+        let start = Instant::now();
+
+        let (tx, rx) = bounded(1);
+        tx.send(chunk).unwrap();
+        drop(tx);
+
+        let _result = compute_hash(
+            rx,
+            &AtomicBool::new(false),
+            Md5::new,
+            |context, data| context.update(data),
+            |context| to_hex(&context.finalize()),
+        );
+
+        let duration = start.elapsed();
+        println!("Hash computation took: {:?}", duration);
+
+        // Verify it takes less than 1 second (adjust as needed)
+        assert!(duration.as_secs() < 1);
+    }
+}
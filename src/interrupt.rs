@@ -0,0 +1,46 @@
+// Import standard library components
+use std::{
+    error::Error,                         // Error trait for the interruption
+    fmt,                                  // Display implementation
+    sync::atomic::{AtomicBool, Ordering}, // Flag shared with the signal handler
+};
+
+/// Exit status of a run stopped by a signal (128 + SIGINT, as shells report it)
+pub const EXIT_CODE: i32 = 130;
+
+/// Set by the signal handler, polled by the reader between chunks
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Stops hashing at the next chunk on Ctrl-C, SIGTERM or SIGHUP
+/// A second signal exits immediately, for readers blocked on a slow device
+pub fn install() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_CODE);
+        }
+    })
+}
+
+/// Flag checked by the hashing pipeline
+pub fn flag() -> &'static AtomicBool {
+    &REQUESTED
+}
+
+/// True once a signal was received
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Error returned by a pipeline stopped by a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted {
+    pub bytes: u64, // Bytes hashed before the reader stopped
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "interrupted after {} bytes", self.bytes)
+    }
+}
+
+impl Error for Interrupted {}
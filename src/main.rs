@@ -1,247 +1,922 @@
 // Import standard library components
 use std::{
     env,                   // Environment variables and command-line arguments
-    fs::File,              // File handling
-    io::{BufReader, Read}, // Buffered reading
-    path::PathBuf,         // Path manipulation
-    sync::Arc,             // Atomic Reference Counted pointer for thread-safe sharing
-    thread,                // Thread management
+    fs,                    // File metadata
+    path::{Path, PathBuf}, // Path manipulation
     time::Instant,         // Time measurement
 };
 
 // External crates
-use crc::Crc; // CRC32 implementation
-use crossbeam_channel::bounded; // Thread communication channels
-use md5::Context; // MD5 hashing context
-use sha1::{Digest, Sha1}; // SHA1 hasher
-use sha2::{Sha256, Sha512}; // SHA256 and SHA512 hashers
-
-//use std::process::Command;
-
-/// Computes a hash by processing data chunks on the fly
-/// Parameters:
-/// - rx: Channel receiver for data chunks
-/// - initializer: Function that initializes the hash context
-/// - updater: Function that updates the context with new data
-/// - finalizer: Function that produces the final hash
-fn compute_hash<H, C, I, U, F>(
-    rx: crossbeam_channel::Receiver<Arc<[u8]>>,
-    initializer: I,
-    updater: U,
-    finalizer: F,
-) -> H
-where
-    I: FnOnce() -> C,
-    U: Fn(&mut C, &[u8]),
-    F: FnOnce(C) -> H,
-{
-    // Initialize the hash context
-    let mut context = initializer();
-
-    // Process each data chunk as it is received
-    while let Ok(chunk) = rx.recv() {
-        updater(&mut context, &chunk);
-    }
-
-    // Finalize the hash
-    finalizer(context)
+use clap::Args; // Arguments of the hash command
+
+// Application modules
+mod archive; // Archives and compressed files
+mod atomic; // Crash-safe file replacement
+mod bench; // Hashing throughput measurement
+mod cache; // Persistent digest cache
+mod check; // Verification against a manifest
+mod checkpoint; // Resumable hashing of large files
+mod cli; // Command-line definitions and parsing
+mod config; // Defaults from the config file and environment
+mod diff; // Directory comparison by content
+mod dupes; // Duplicate file finder
+mod error; // Typed errors and exit codes
+mod find; // Search for files matching given digests
+mod forensic; // Acquisition tolerating read errors
+mod formats; // Internal CRCs of zip, gzip and PNG files
+mod hashdeep; // hashdeep-compatible files and audits
+mod hasher; // Multi-algorithm hashing pipeline
+mod interrupt; // Ctrl-C and SIGTERM handling
+mod manifest; // Aggregate manifests (SHA256SUMS-style)
+mod report; // Acquisition reports (DFXML, chain of custody)
+mod scrub; // Periodic bit-rot detection
+mod walk; // Recursive directory traversal
+mod watch; // Rehashing files as they change
+mod xattrs; // Checksums stored in extended attributes
+
+use cache::HashCache;
+use cli::Command;
+use config::Config;
+use error::SlashsumError;
+use forensic::ForensicOptions;
+use hasher::{
+    Algorithm, Checksums, FileState, hash_file, hash_file_range, parse_algorithms, parse_digest,
+};
+use manifest::{ManifestFormat, ManifestOptions, collect_inputs, write_manifests};
+use report::{Report, ReportFormat, Source};
+use xattrs::XattrStatus;
+
+/// Release version, set by the build environment
+pub const BUILD_VERSION: &str = match option_env!("BUILD_VERSION") {
+    Some(version) => version,
+    None => "dev",
+};
+
+/// Commit the binary was built from, set by the build environment
+pub const GIT_COMMIT: &str = match option_env!("GIT_COMMIT") {
+    Some(commit) => commit,
+    None => "unknown",
+};
+
+/// Parsed command-line options
+struct Options {
+    files: Vec<String>,
+    save: bool,
+    expect: Option<String>, // Expected digest, algorithm detected from its length
+    cache: bool,            // Reuse digests of unchanged files from the cache
+    rehash: bool,           // Ignore cached digests but refresh them
+    manifest: Option<ManifestOptions>,
+    xattr_store: bool,   // Write digests and mtime into user.checksum.* attributes
+    xattr_verify: bool,  // Check digests against user.checksum.* attributes
+    archive: bool,       // Also hash the files stored in zip/tar archives
+    check_format: bool,  // Verify the CRCs stored inside zip, gzip and PNG files
+    decompress: bool,    // Hash the decompressed content of compressed files
+    both: bool,          // With decompress, also hash the compressed file
+    offset: Option<u64>, // Start of the byte range to hash
+    length: Option<u64>, // Length of the byte range to hash
+    lock: bool,          // Hold a shared advisory lock while hashing
+    retry: u32,          // Times to rehash a file that changed while being hashed
+    checkpoint: Option<PathBuf>, // File where hashing progress is saved
+    resume: bool,        // Continue from the saved progress
+    forensic: Option<ForensicOptions>, // Tolerate read errors, hash segments
+    report: Option<ReportFormat>, // Acquisition report of the run
+    report_file: Option<PathBuf>, // Where the report is written
+    output: Output,
+    algorithms: Vec<Algorithm>, // Digests printed by --quiet and --porcelain
 }
 
-// Structure to encapsulate CRC32 calculation
-struct Crc32Calculator {
-    crc_algo: Crc<u32>,
-    data: Vec<u8>,
+/// How the results of each file are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Normal,    // Labelled blocks for people
+    Quiet,     // Digests alone, one per line
+    Porcelain, // Stable tab-separated lines for scripts
 }
 
-impl Crc32Calculator {
-    fn new() -> Self {
-        Self {
-            crc_algo: Crc::<u32>::new(&crc::CRC_32_ISO_HDLC),
-            data: Vec::new(),
+impl Output {
+    /// Parses an output mode name, as set in the config file
+    fn from_name(name: &str) -> Result<Output, String> {
+        match name {
+            "normal" => Ok(Output::Normal),
+            "quiet" => Ok(Output::Quiet),
+            "porcelain" => Ok(Output::Porcelain),
+            _ => Err(format!(
+                "Unknown output '{}' (use normal, quiet or porcelain)",
+                name
+            )),
         }
     }
+}
 
-    fn update(&mut self, new_data: &[u8]) {
-        // Append new data
-        self.data.extend_from_slice(new_data);
+impl Options {
+    /// True when only part of each file is hashed
+    fn has_range(&self) -> bool {
+        self.offset.is_some() || self.length.is_some()
     }
+}
+
+/// Arguments of the `hash` command, also used when no command is given
+#[derive(Args, Debug)]
+pub struct HashArgs {
+    /// Files to hash (directories too, with --manifest)
+    #[arg(value_name = "FILE", required = true)]
+    files: Vec<String>,
+
+    /// Save checksums to a .checksum file next to each file
+    #[arg(short, long)]
+    save: bool,
+
+    /// Do not save .checksum files, overriding --save and the config file
+    #[arg(long)]
+    no_save: bool,
+
+    /// Print only the digests, one per line (all algorithms, or those of --algo)
+    #[arg(short, long, conflicts_with = "porcelain")]
+    quiet: bool,
+
+    /// Print "ALGO<TAB>DIGEST<TAB>SIZE<TAB>PATH" lines, a format that will not change
+    #[arg(long)]
+    porcelain: bool,
+
+    /// Compare with an expected digest (algorithm detected from its length)
+    #[arg(short, long, value_name = "HEX", value_parser = parse_digest)]
+    expect: Option<String>,
+
+    /// Also hash each file stored in a zip, tar, tar.gz, tar.xz, tar.zst or tar.bz2
+    /// archive, without extracting it
+    #[arg(long)]
+    archive: bool,
+
+    /// Verify the CRCs stored in zip entries, gzip trailers and PNG chunks
+    /// (exit code 1 if one is corrupt)
+    #[arg(long)]
+    check_format: bool,
+
+    /// Hash the decompressed content of gzip, xz, zstd and bzip2 files
+    #[arg(long)]
+    decompress: bool,
+
+    /// With --decompress, also hash the compressed file (same read)
+    #[arg(long)]
+    both: bool,
+
+    /// Start hashing at this byte offset (suffixes: K, M, G, T, KB, MB, ...)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    offset: Option<u64>,
+
+    /// Hash at most this many bytes
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    length: Option<u64>,
+
+    /// Save progress to FILE every GiB, so an interrupted run can resume
+    #[arg(long, value_name = "FILE", value_parser = parse_path)]
+    checkpoint: Option<PathBuf>,
+
+    /// Continue from the progress saved in FILE (same digests as one run)
+    #[arg(long, value_name = "FILE", value_parser = parse_path)]
+    resume: Option<PathBuf>,
+
+    /// Hold a shared advisory lock on each file while hashing it
+    #[arg(short, long)]
+    lock: bool,
+
+    /// Do not lock files, overriding --lock and the config file
+    #[arg(long)]
+    no_lock: bool,
+
+    /// Rehash a file up to N times if it changes while being hashed
+    /// (default 0, exit code 3 if it still changed)
+    #[arg(long, value_name = "N")]
+    retry: Option<u32>,
+
+    /// Store digests and mtime in user.checksum.* extended attributes
+    #[arg(long)]
+    xattr_store: bool,
+
+    /// Check digests stored in extended attributes (exit code 1 if stale,
+    /// corrupted or missing)
+    #[arg(long)]
+    xattr_verify: bool,
+
+    /// Keep going on read errors: retry, then zero-fill and log unreadable
+    /// sectors; also hash each segment separately
+    #[arg(long, help_heading = "Forensic options")]
+    forensic: bool,
+
+    /// With --forensic, size of the separately hashed segments (default 1G)
+    #[arg(long = "segment", value_name = "SIZE", value_parser = parse_size, help_heading = "Forensic options")]
+    segment_size: Option<u64>,
+
+    /// With --forensic, write a copy of the data read to FILE
+    #[arg(long, value_name = "FILE", value_parser = parse_path, help_heading = "Forensic options")]
+    image: Option<PathBuf>,
+
+    /// With --forensic, log unreadable ranges (offset, length, error)
+    #[arg(long, value_name = "FILE", value_parser = parse_path, help_heading = "Forensic options")]
+    bad_blocks: Option<PathBuf>,
+
+    /// Write an acquisition report: dfxml (Digital Forensics XML) or text
+    /// (chain of custody with sign-off lines)
+    #[arg(long, value_name = "FORMAT", value_parser = ReportFormat::from_name, help_heading = "Forensic options")]
+    report: Option<ReportFormat>,
+
+    /// Report location (default: slashsum-report-<start time>.xml/.txt)
+    #[arg(long, value_name = "FILE", value_parser = parse_path, help_heading = "Forensic options")]
+    report_file: Option<PathBuf>,
+
+    /// Write all results to one manifest (directories are walked)
+    #[arg(short, long, value_name = "FILE", value_parser = parse_path, help_heading = "Manifest options")]
+    manifest: Option<PathBuf>,
+
+    /// Manifest format: sums (default) or hashdeep
+    #[arg(long, value_name = "FORMAT", value_parser = ManifestFormat::from_name, help_heading = "Manifest options")]
+    format: Option<ManifestFormat>,
+
+    /// Write paths relative to DIR
+    #[arg(long, value_name = "DIR", value_parser = parse_path, help_heading = "Manifest options")]
+    base: Option<PathBuf>,
+
+    /// Prepend PATH to every written path
+    #[arg(long, value_name = "PATH", help_heading = "Manifest options")]
+    prefix: Option<String>,
+
+    /// Algorithms to include, comma-separated (default: all; md5,sha256 for hashdeep);
+    /// also selects the digests printed by --quiet and --porcelain
+    #[arg(
+        short,
+        long = "algo",
+        value_name = "LIST",
+        help_heading = "Manifest options"
+    )]
+    algorithms: Option<String>,
+
+    /// Write one <ALGO>SUMS file per algorithm into <FILE> as a directory
+    #[arg(long, help_heading = "Manifest options")]
+    split: bool,
+
+    /// Merge into an existing manifest (locked for concurrent runs)
+    #[arg(long, help_heading = "Manifest options")]
+    append: bool,
+
+    /// Reuse digests of unchanged files (same device, inode, size, mtime, ctime)
+    #[arg(short, long, help_heading = "Cache options")]
+    cache: bool,
+
+    /// Ignore cached digests but refresh them (implies --cache)
+    #[arg(long, help_heading = "Cache options")]
+    rehash: bool,
+
+    /// Disable the cache, overriding --cache
+    #[arg(long, help_heading = "Cache options")]
+    no_cache: bool,
+}
+
+impl HashArgs {
+    /// Checks the combinations of options, then fills in the configured
+    /// defaults that apply to this run
+    fn into_options(self, config: &Config) -> Result<Options, String> {
+        let HashArgs {
+            files,
+            save,
+            no_save,
+            quiet,
+            porcelain,
+            expect,
+            archive,
+            check_format,
+            decompress,
+            both,
+            offset,
+            length,
+            checkpoint,
+            resume,
+            lock,
+            no_lock,
+            retry,
+            xattr_store,
+            xattr_verify,
+            forensic,
+            segment_size,
+            image,
+            bad_blocks,
+            report,
+            report_file,
+            manifest,
+            format,
+            base,
+            prefix,
+            algorithms,
+            split,
+            append,
+            cache,
+            rehash,
+            no_cache,
+        } = self;
+
+        let output = if porcelain {
+            Output::Porcelain
+        } else if quiet {
+            Output::Quiet
+        } else {
+            match config.output {
+                // Porcelain lines are only printed for single files
+                Some(Output::Porcelain) if manifest.is_some() => Output::Normal,
+                configured => configured.unwrap_or(Output::Normal),
+            }
+        };
+        let manifest = match manifest {
+            Some(path) => {
+                let format = format.or(config.format).unwrap_or(ManifestFormat::Sums);
+                let algorithms = match format {
+                    ManifestFormat::Sums => {
+                        match algorithms.clone().or_else(|| config.algorithms.clone()) {
+                            Some(list) => parse_algorithms(&list)?,
+                            None => Algorithm::ALL.to_vec(),
+                        }
+                    }
+                    ManifestFormat::Hashdeep => {
+                        if split {
+                            return Err(
+                                "--split cannot be used with the hashdeep format".to_string()
+                            );
+                        }
+                        match (&algorithms, &config.algorithms) {
+                            (None, Some(list)) => hashdeep::configured_algorithms(list),
+                            _ => hashdeep::parse_hashdeep_algorithms(algorithms.as_deref())?,
+                        }
+                    }
+                };
+                Some(ManifestOptions {
+                    path,
+                    format,
+                    base,
+                    prefix,
+                    algorithms,
+                    split,
+                    append,
+                })
+            }
+            None => {
+                if format.is_some() || base.is_some() || prefix.is_some() || split || append {
+                    return Err(
+                        "--format, --base, --prefix, --split and --append require --manifest"
+                            .to_string(),
+                    );
+                }
+                if algorithms.is_some() && output == Output::Normal {
+                    return Err("--algo requires --manifest, --quiet or --porcelain".to_string());
+                }
+                None
+            }
+        };
+        let printed = match (&manifest, algorithms.or_else(|| config.algorithms.clone())) {
+            (None, Some(list)) if output != Output::Normal => parse_algorithms(&list)?,
+            _ => Algorithm::ALL.to_vec(),
+        };
+
+        if porcelain && manifest.is_some() {
+            return Err("--porcelain cannot be combined with --manifest".to_string());
+        }
+        if save && manifest.is_some() {
+            return Err("--save cannot be combined with --manifest".to_string());
+        }
+        if expect.is_some() && manifest.is_some() {
+            return Err("--expect cannot be combined with --manifest".to_string());
+        }
+        if archive && manifest.is_some() {
+            return Err("--archive cannot be combined with --manifest".to_string());
+        }
+        if check_format && manifest.is_some() {
+            return Err("--check-format cannot be combined with --manifest".to_string());
+        }
+        if (offset.is_some() || length.is_some())
+            && (manifest.is_some()
+                || archive
+                || decompress
+                || check_format
+                || xattr_store
+                || xattr_verify)
+        {
+            return Err(
+                "--offset and --length cannot be combined with --manifest, --archive, \
+             --decompress, --check-format or --xattr-*"
+                    .to_string(),
+            );
+        }
+        if both && !decompress {
+            return Err("--both requires --decompress".to_string());
+        }
+        if decompress && (manifest.is_some() || archive || xattr_store || xattr_verify) {
+            return Err(
+                "--decompress cannot be combined with --manifest, --archive or --xattr-*"
+                    .to_string(),
+            );
+        }
+        if checkpoint.is_some() && resume.is_some() {
+            return Err("--resume continues writing its checkpoint: use it alone".to_string());
+        }
+        if checkpoint.is_some() || resume.is_some() {
+            if files.len() > 1 {
+                return Err("--checkpoint and --resume take a single file".to_string());
+            }
+            if manifest.is_some()
+                || archive
+                || decompress
+                || offset.is_some()
+                || length.is_some()
+                || cache
+                || rehash
+            {
+                return Err(
+                    "--checkpoint and --resume cannot be combined with --manifest, --archive, \
+                 --decompress, --offset, --length or --cache"
+                        .to_string(),
+                );
+            }
+        }
+        let forensic = if forensic {
+            if manifest.is_some()
+                || archive
+                || decompress
+                || offset.is_some()
+                || length.is_some()
+                || checkpoint.is_some()
+                || resume.is_some()
+            {
+                return Err(
+                    "--forensic cannot be combined with --manifest, --archive, --decompress, \
+                 --offset, --length, --checkpoint or --resume"
+                        .to_string(),
+                );
+            }
+            if (image.is_some() || bad_blocks.is_some()) && files.len() > 1 {
+                return Err("--image and --bad-blocks take a single file".to_string());
+            }
+            if segment_size == Some(0) {
+                return Err("Segment size must not be zero".to_string());
+            }
+            Some(ForensicOptions {
+                segment_size: segment_size.unwrap_or(forensic::SEGMENT_SIZE),
+                image,
+                bad_blocks,
+            })
+        } else {
+            if segment_size.is_some() || image.is_some() || bad_blocks.is_some() {
+                return Err("--segment, --image and --bad-blocks require --forensic".to_string());
+            }
+            None
+        };
+        if report.is_some() && manifest.is_some() {
+            return Err("--report cannot be combined with --manifest".to_string());
+        }
+        if report_file.is_some() && report.is_none() {
+            return Err("--report-file requires --report".to_string());
+        }
+        let save = save && !no_save;
+        let lock = lock && !no_lock;
+        if (lock || retry.is_some_and(|retry| retry > 0)) && manifest.is_some() {
+            return Err("--lock and --retry cannot be combined with --manifest".to_string());
+        }
+        if (xattr_store || xattr_verify) && manifest.is_some() {
+            return Err(
+                "--xattr-store and --xattr-verify cannot be combined with --manifest".to_string(),
+            );
+        }
+        if xattr_verify && (cache || rehash) && !no_cache {
+            // Cached digests would hide the corruption being looked for
+            return Err("--xattr-verify cannot be combined with --cache".to_string());
+        }
 
-    fn finalize(self) -> u32 {
-        // Calculate CRC32 on all accumulated data
-        self.crc_algo.checksum(&self.data)
+        // Configured defaults never conflict with the options given
+        let single = manifest.is_none();
+        let cache_allowed = !xattr_verify && checkpoint.is_none() && resume.is_none();
+        Ok(Options {
+            files,
+            save: (save || (config.save && single)) && !no_save,
+            expect,
+            cache: (cache || rehash || (config.cache && cache_allowed)) && !no_cache,
+            rehash,
+            manifest,
+            xattr_store,
+            xattr_verify,
+            archive,
+            check_format,
+            decompress,
+            both,
+            offset,
+            length,
+            lock: (lock || (config.lock && single)) && !no_lock,
+            retry: retry.or(config.retry.filter(|_| single)).unwrap_or(0),
+            checkpoint: resume.clone().or(checkpoint),
+            resume: resume.is_some(),
+            forensic,
+            report,
+            report_file,
+            output,
+            algorithms: printed,
+        })
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!(
-        "slashsum {} - {}",
-        option_env!("BUILD_VERSION").unwrap_or("dev"),
-        option_env!("GIT_COMMIT").unwrap_or("unknown")
-    );
+fn main() {
+    // Exit quietly when the reader of a pipe goes away (slashsum -q file | head -1)
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
 
-    // Get command-line arguments
+    // Get command-line arguments (also recorded in acquisition reports)
     let args: Vec<String> = env::args().collect();
+    let cli = cli::parse();
 
-    // Handle version and license request
-    if args.iter().any(|arg| arg == "--version") {
-        print_version_and_license();
-        return Ok(());
+    // On stderr, so that results can be piped and compared
+    if cli.verbose {
+        eprintln!("slashsum {} - {}", BUILD_VERSION, GIT_COMMIT);
     }
 
-    // Handle help request
-    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
-        print_help();
-        return Ok(());
+    if let Err(error) = run(cli, &args) {
+        match &error {
+            SlashsumError::Usage(message) => cli::exit_usage(message),
+            _ => {
+                if let Some(message) = error.message() {
+                    eprintln!("slashsum: error: {}", message);
+                }
+                std::process::exit(error.exit_code());
+            }
+        }
     }
+}
+
+/// Converts the result of a subcommand that reports failures as false
+fn check_result(result: Result<bool, Box<dyn std::error::Error>>) -> Result<(), SlashsumError> {
+    match result? {
+        true => Ok(()),
+        false => Err(SlashsumError::Mismatch),
+    }
+}
+
+/// Runs the command given on the command line
+fn run(cli: cli::Cli, args: &[String]) -> Result<(), SlashsumError> {
+    // Dispatch subcommands
+    let hash_args = match cli.command {
+        None => cli.hash,
+        Some(Command::Hash(hash_args)) => *hash_args,
+        Some(Command::Check(options)) => return check_result(check::run(&options)),
+        Some(Command::Find(find_args)) => {
+            let options = find_args.into_options()?;
+            return check_result(find::run(&options));
+        }
+        Some(Command::Dupes(dupes_args)) => return Ok(dupes::run(&dupes_args.into_options())?),
+        Some(Command::Diff(options)) => return check_result(diff::run(&options)),
+        Some(Command::Audit(options)) => return check_result(hashdeep::run(&options)),
+        Some(Command::Scrub(scrub_args)) => {
+            return check_result(scrub::run(&scrub_args.into_options()));
+        }
+        Some(Command::Watch(options)) => return Ok(watch::run(&options)?),
+        Some(Command::Cache(options)) => return Ok(cache::run(&options)?),
+        Some(Command::Config(options)) => return Ok(config::run(&options)?),
+        Some(Command::Bench(options)) => return Ok(bench::run(&options)?),
+        Some(Command::Completions(options)) => return Ok(cli::print_completions(&options)?),
+        Some(Command::Man(options)) => return Ok(cli::write_man(&options)?),
+    };
+
+    // Validate arguments
+    let config = Config::load()?;
+    let options = hash_args
+        .into_options(&config)
+        .map_err(SlashsumError::Usage)?;
+
+    // Expand tilde and validate input files exist
+    let mut file_paths = Vec::new();
+    for arg in &options.files {
+        let file_path = expand_tilde(arg);
+        if !file_path.exists() {
+            return Err(SlashsumError::NotFound(format!("File '{}' not found", arg)));
+        }
+        if file_path.is_dir() && options.manifest.is_none() {
+            return Err(SlashsumError::Usage(format!(
+                "'{}' is a directory (use --manifest to hash directory trees)",
+                arg
+            )));
+        }
+        file_paths.push(file_path);
+    }
+
+    // Open the digest cache when enabled
+    let mut cache = if options.cache {
+        let path = cache::default_path().ok_or(SlashsumError::Read(
+            "Cannot determine the cache directory".to_string(),
+        ))?;
+        Some(HashCache::open(path, options.rehash)?)
+    } else {
+        None
+    };
+
+    // Stop at the next chunk on Ctrl-C or SIGTERM, and report what was done
+    interrupt::install()
+        .map_err(|e| SlashsumError::Read(format!("Cannot install the signal handler: {}", e)))?;
+    let run_start = Instant::now();
+
+    if let Some(manifest) = &options.manifest {
+        let quiet = options.output == Output::Quiet;
+        let mut bytes_done = 0;
+        if let Err(e) = run_manifest(
+            manifest,
+            &file_paths,
+            cache.as_mut(),
+            quiet,
+            &mut bytes_done,
+        ) {
+            if interrupt::requested() {
+                save_cache(cache.as_ref())?;
+                return Err(report_interrupted(&*e, None, bytes_done, run_start));
+            }
+            return Err(e.into());
+        }
+        return Ok(save_cache(cache.as_ref())?);
+    }
+
+    let mut mismatch = false;
+    let mut bytes_done = 0;
+    let mut changed_any = false;
+    let mut report = options.report.map(|format| Report::new(format, args));
+    let normal = options.output == Output::Normal;
+    for (index, file_path) in file_paths.iter().enumerate() {
+        if index > 0 && normal {
+            println!();
+        }
+        let mtime = fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| SlashsumError::io(format!("'{}'", file_path.display()), &e))?;
+        let source = match report {
+            Some(_) => Some(
+                Source::identify(file_path)
+                    .map_err(|e| SlashsumError::io(format!("'{}'", file_path.display()), &e))?,
+            ),
+            None => None,
+        };
+        let hashed = match hash_and_print(file_path, &options, cache.as_mut()) {
+            Ok(hashed) => hashed,
+            Err(e) if interrupt::requested() => {
+                save_cache(cache.as_ref())?;
+                return Err(report_interrupted(
+                    &*e,
+                    Some(file_path),
+                    bytes_done,
+                    run_start,
+                ));
+            }
+            Err(e) => return Err(SlashsumError::for_file(file_path, e)),
+        };
+        let checksums = hashed.checksums;
+        bytes_done += checksums.size;
+        if hashed.changed {
+            changed_any = true;
+        }
+        if let (Some(report), Some(source)) = (&mut report, source) {
+            report.add(
+                source,
+                checksums.clone(),
+                hashed.changed,
+                hashed.acquisition,
+            );
+        }
+
+        // Handle --check-format flag
+        if options.check_format {
+            match formats::check_format(file_path)? {
+                Some(report) => {
+                    if normal {
+                        println!("Format: {}", report.describe());
+                        for error in &report.errors {
+                            println!("  {}", error);
+                        }
+                    }
+                    if !report.errors.is_empty() {
+                        mismatch = true;
+                    }
+                }
+                None if normal => println!("Format: not checked (not a zip, gzip or PNG file)"),
+                None => {}
+            }
+        }
+
+        // Handle --xattr-store and --xattr-verify flags
+        if options.xattr_store && hashed.changed {
+            eprintln!(
+                "Warning: '{}' changed during hashing, extended attributes not stored",
+                file_path.display()
+            );
+        } else if options.xattr_store && store_xattrs(file_path, &checksums, mtime)? && normal {
+            println!("Extended attributes: stored");
+        }
+        if options.xattr_verify {
+            let stored = xattrs::read(file_path)?;
+            let status = xattrs::classify(&stored, &checksums, &xattrs::format_mtime(mtime));
+            if normal {
+                println!("Extended attributes: {}", status.describe());
+            }
+            if !matches!(status, XattrStatus::Ok | XattrStatus::TimestampChanged) {
+                mismatch = true;
+            }
+        }
 
-    // Validate argument count
-    if args.len() < 2 || args.len() > 3 {
-        eprintln!("Error: Invalid number of arguments");
-        eprintln!("Use -h or --help for usage instructions");
-        std::process::exit(1);
+        // Handle --expect flag
+        if let Some(expected) = &options.expect {
+            match checksums.find_match(expected) {
+                Some(algo) if normal => println!("Expected: MATCH ({})", algo.name()),
+                Some(_) => {}
+                None => {
+                    let checked: Vec<&str> = Algorithm::candidates(expected)
+                        .into_iter()
+                        .map(Algorithm::name)
+                        .collect();
+                    if normal {
+                        println!("Expected: NO MATCH (checked {})", checked.join(", "));
+                    }
+                    mismatch = true;
+                }
+            }
+        }
     }
 
-    // Check for --save flag
-    let save_flag = if args.len() == 3 {
-        if args[2] != "--save" {
-            eprintln!("Error: Invalid option '{}'", args[2]);
-            std::process::exit(1);
+    // Handle --report flag
+    if let Some(report) = &report {
+        let path = options
+            .report_file
+            .clone()
+            .unwrap_or_else(|| report.default_path());
+        report.write(&path).map_err(|e| {
+            SlashsumError::io(format!("Cannot write report '{}'", path.display()), &e)
+        })?;
+        if normal {
+            println!();
+            println!("Report saved to: {}", path.display());
         }
-        true
+    }
+
+    save_cache(cache.as_ref())?;
+    if changed_any {
+        // Distinct from mismatches: rerunning later may give consistent digests
+        return Err(SlashsumError::Changed);
+    }
+    if mismatch {
+        return Err(SlashsumError::Mismatch);
+    }
+
+    Ok(())
+}
+
+/// Reports how far a run stopped by a signal went
+/// Nothing was printed or saved for the interrupted file, so no partial
+/// .checksum file or manifest is left behind
+fn report_interrupted(
+    error: &(dyn std::error::Error + 'static),
+    file_path: Option<&Path>,
+    bytes_done: u64,
+    run_start: Instant,
+) -> SlashsumError {
+    let partial = error
+        .downcast_ref::<interrupt::Interrupted>()
+        .map_or(0, |interrupted| interrupted.bytes);
+    let total = bytes_done + partial;
+    let seconds = run_start.elapsed().as_secs_f64();
+    let rate = if seconds > 0.0 {
+        total as f64 / seconds / 1_048_576.0
     } else {
-        false
+        0.0
     };
 
-    // Expand tilde and validate input file exists
-    let file_path = expand_tilde(&args[1]);
-    if !file_path.exists() {
-        eprintln!("Error: File '{}' not found", args[1]);
-        eprintln!("Use -h or --help for usage instructions");
-        std::process::exit(1);
+    eprintln!();
+    match file_path {
+        Some(path) => eprintln!(
+            "Interrupted while hashing '{}' after reading {}",
+            path.display(),
+            format_size(partial)
+        ),
+        None => eprintln!("Interrupted"),
+    }
+    eprintln!(
+        "Processed: {} in {:.2?} ({:.1} MB/s)",
+        format_size(total),
+        run_start.elapsed(),
+        rate
+    );
+    SlashsumError::Interrupted
+}
+
+/// Hashes a file, through the digest cache when it is enabled
+fn hash_path(
+    path: &Path,
+    cache: Option<&mut HashCache>,
+) -> Result<Checksums, Box<dyn std::error::Error>> {
+    match cache {
+        Some(cache) => cache.hash(path),
+        None => hash_file(path),
+    }
+}
+
+/// Writes new cache entries back; a cache that cannot be written is not fatal
+fn save_cache(cache: Option<&HashCache>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(cache) = cache
+        && let Err(e) = cache.save()
+    {
+        eprintln!("Warning: cannot write cache: {}", e);
     }
+    Ok(())
+}
 
+/// Result of hashing one input file
+struct Hashed {
+    checksums: Checksums,
+    changed: bool,                              // Modified while being hashed
+    acquisition: Option<forensic::Acquisition>, // Segments and bad blocks (--forensic)
+}
+
+/// Hashes one file, prints the results and optionally saves them
+fn hash_and_print(
+    file_path: &Path,
+    options: &Options,
+    mut cache: Option<&mut HashCache>,
+) -> Result<Hashed, Box<dyn std::error::Error>> {
     // Start performance timer
     let start_time = Instant::now();
 
-    // Open file and create buffered reader
-    let file = File::open(&file_path)?;
-    let metadata = file.metadata()?;
-    let size = metadata.len();
-    let mut reader = BufReader::with_capacity(1_048_576, file); // 1MB buffer
-
-    // Create communication channels for each hash algorithm
-    let (crc32_tx, crc32_rx) = bounded(1024); // CRC32 channel
-    let (md5_tx, md5_rx) = bounded(1024); // MD5 channel
-    let (sha1_tx, sha1_rx) = bounded(1024); // SHA1 channel
-    let (sha256_tx, sha256_rx) = bounded(1024); // SHA256 channel
-    let (sha512_tx, sha512_rx) = bounded(1024); // SHA512 channel
-
-    // Spawn thread for CRC32 calculation
-    let crc32_handle = thread::spawn(move || {
-        compute_hash(
-            crc32_rx,
-            || Crc32Calculator::new(),
-            |calculator, data| calculator.update(data),
-            |calculator| format!("{:08x}", calculator.finalize()),
-        )
-    });
-
-    // Spawn thread for MD5 calculation
-    let md5_handle = thread::spawn(move || {
-        compute_hash(
-            md5_rx,
-            || Context::new(),                            // initialize MD5 context
-            |context, data| context.consume(data),        // update with data
-            |context| format!("{:x}", context.compute()), // finalize and format
-        )
-    });
-
-    // Spawn thread for SHA1 calculation
-    let sha1_handle = thread::spawn(move || {
-        compute_hash(
-            sha1_rx,
-            || Sha1::new(), // initialize SHA1 context
-            |digest, data| {
-                digest.update(data);
-            }, // update with data
-            |digest| format!("{:x}", digest.finalize()), // finalize and format
-        )
-    });
-
-    // Spawn thread for SHA256 calculation
-    let sha256_handle = thread::spawn(move || {
-        compute_hash(
-            sha256_rx,
-            || Sha256::new(), // initialize SHA256 context
-            |digest, data| {
-                digest.update(data);
-            }, // update with data
-            |digest| format!("{:x}", digest.finalize()), // finalize and format
-        )
-    });
-
-    // Spawn thread for SHA512 calculation
-    let sha512_handle = thread::spawn(move || {
-        compute_hash(
-            sha512_rx,
-            || Sha512::new(), // initialize SHA512 context
-            |digest, data| {
-                digest.update(data);
-            }, // update with data
-            |digest| format!("{:x}", digest.finalize()), // finalize and format
-        )
-    });
-
-    // Read file in 1MB chunks
-    loop {
-        let mut buffer = vec![0; 1_048_576]; // 1MB buffer
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            // End of file
-            break;
-        }
-        buffer.truncate(bytes_read);
-        let chunk = Arc::from(buffer.into_boxed_slice());
-
-        // Send chunk to all hash channels
-        crc32_tx.send(Arc::clone(&chunk))?;
-        md5_tx.send(Arc::clone(&chunk))?;
-        sha1_tx.send(Arc::clone(&chunk))?;
-        sha256_tx.send(Arc::clone(&chunk))?;
-        sha512_tx.send(chunk)?; // Final send uses original Arc
-    }
-
-    // Close all transmission channels
-    drop(crc32_tx);
-    drop(md5_tx);
-    drop(sha1_tx);
-    drop(sha256_tx);
-    drop(sha512_tx);
-
-    // Collect results from all threads
-    let crc32 = crc32_handle.join().map_err(|_| "Thread CRC32 error")?;
-    let md5 = md5_handle.join().map_err(|_| "Thread MD5 error")?;
-    let sha1 = sha1_handle.join().map_err(|_| "Thread SHA1 error")?;
-    let sha256 = sha256_handle.join().map_err(|_| "Thread SHA256 error")?;
-    let sha512 = sha512_handle.join().map_err(|_| "Thread SHA512 error")?;
+    // Hash the file, again while it keeps changing and retries are left
+    let mut attempt = 0;
+    let (contents, changed) = loop {
+        // Capture the state once the lock is held, so a writer finishing
+        // while waiting for it is not mistaken for a change
+        let lock = if options.lock {
+            Some(lock_shared(file_path)?)
+        } else {
+            None
+        };
+        let before = FileState::of(file_path)?;
+        let contents = hash_contents(file_path, options, cache.as_deref_mut())?;
+        drop(lock);
+
+        let changed = before.is_some() && FileState::of(file_path)? != before;
+        if !changed || attempt == options.retry {
+            break (contents, changed);
+        }
+        attempt += 1;
+        eprintln!(
+            "Warning: '{}' changed during hashing, retrying ({}/{})",
+            file_path.display(),
+            attempt,
+            options.retry
+        );
+    };
 
     // Format final output
-    let output = format!(
-        "File: {}\nSize:    {}\nCRC32:   {}\nMD5:   {}\nSHA1:  {}\nSHA256: {}\nSHA512: {}\nTime:  {:.2?}",
-        file_path.display(),
-        format_size(size),
-        crc32,
-        md5,
-        sha1,
-        sha256,
-        sha512,
-        start_time.elapsed()
-    );
-    println!("{}", output);
+    let Contents {
+        checksums,
+        extra,
+        acquisition,
+    } = contents;
+    let mut details = Vec::new();
+    if let Some(kind) = describe_special(file_path, &checksums, options.has_range())? {
+        details.push(format!("Type:    {}", kind));
+    }
+    if options.has_range() {
+        let offset = options.offset.unwrap_or(0);
+        details.push(format!(
+            "Range:   offset {}, length {}",
+            offset, checksums.size
+        ));
+    }
+    if changed {
+        details.push(
+            "Status:  CHANGED DURING HASHING (digests of an inconsistent snapshot)".to_string(),
+        );
+    }
+    let mut output = format_output(file_path, &checksums, &details, start_time);
+    for block in &extra {
+        output.push_str("\n\n");
+        output.push_str(block);
+    }
+    match options.output {
+        Output::Normal => println!("{}", output),
+        Output::Quiet => {
+            for algo in &options.algorithms {
+                println!("{}", checksums.get(*algo));
+            }
+        }
+        Output::Porcelain => print!(
+            "{}",
+            format_porcelain(file_path, &checksums, &options.algorithms)
+        ),
+    }
 
-    // Handle --save flag
-    if save_flag {
-        let path = &file_path;
+    // Handle --save flag, never saving digests of an inconsistent snapshot
+    if options.save && changed {
+        eprintln!(
+            "Warning: '{}' changed during hashing, checksums not saved",
+            file_path.display()
+        );
+    } else if options.save {
+        let path = file_path;
         let file_name = path
             .file_name()
             .ok_or("Invalid file name")?
@@ -251,35 +926,283 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         output_path.set_file_name(format!("{}.checksum", file_name));
         std::fs::write(&output_path, output)?;
 
-        println!("Checksums saved to: {}", output_path.display());
+        if options.output == Output::Normal {
+            println!("Checksums saved to: {}", output_path.display());
+        }
+    }
+
+    Ok(Hashed {
+        checksums,
+        changed,
+        acquisition,
+    })
+}
+
+/// Takes a shared advisory lock on a file, waiting while a writer holds it
+fn lock_shared(file_path: &Path) -> Result<fs::File, Box<dyn std::error::Error>> {
+    let file = fs::File::open(file_path)?;
+    match file.try_lock_shared() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => {
+            eprintln!("Waiting for the lock on '{}'...", file_path.display());
+            file.lock_shared()?;
+        }
+        Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+    }
+    Ok(file)
+}
+
+/// Digests of a file's contents, and what else was found while reading it
+struct Contents {
+    checksums: Checksums,
+    extra: Vec<String>, // Additional result blocks to print
+    acquisition: Option<forensic::Acquisition>, // Segments and bad blocks (--forensic)
+}
+
+/// Hashes the file (or reuses its cached digests), and the files it stores
+fn hash_contents(
+    file_path: &Path,
+    options: &Options,
+    cache: Option<&mut HashCache>,
+) -> Result<Contents, Box<dyn std::error::Error>> {
+    let mut extra = Vec::new();
+    let mut acquisition = None;
+    let checksums = if options.archive {
+        let (checksums, members) = archive::hash_archive(file_path)?;
+        for member in members {
+            extra.push(format_entry("Member", &member.path, &member.checksums));
+        }
+        checksums
+    } else if options.decompress {
+        let result = archive::hash_decompressed(file_path, options.both)?;
+        extra.push(format!("Decompressed: {}", result.compression.name()));
+        if let Some(compressed) = &result.compressed {
+            let name = file_path.display().to_string();
+            extra.push(format_entry("Compressed", &name, compressed));
+        }
+        result.content
+    } else if options.has_range() {
+        hash_file_range(file_path, options.offset.unwrap_or(0), options.length)?
+    } else if let Some(forensic) = &options.forensic {
+        let result = forensic::acquire(file_path, forensic)?;
+        for (index, segment) in result.segments.iter().enumerate() {
+            let name = format!("{} (offset {})", index + 1, segment.offset);
+            extra.push(format_entry("Segment", &name, &segment.checksums));
+        }
+        let zeroed: u64 = result.bad.iter().map(|range| range.length).sum();
+        let mut report = format!(
+            "Bad blocks: {} ({} zero-filled)",
+            result.bad.len(),
+            format_size(zeroed)
+        );
+        for range in &result.bad {
+            report.push_str(&format!(
+                "\n  offset {}, length {}",
+                range.offset, range.length
+            ));
+        }
+        if let Some(image) = &forensic.image {
+            report.push_str(&format!("\nImage: {}", image.display()));
+        }
+        extra.push(report);
+        let checksums = result.checksums.clone();
+        acquisition = Some(result);
+        checksums
+    } else if let Some(path) = &options.checkpoint {
+        checkpoint::hash_with_checkpoints(file_path, path, options.resume, checkpoint::INTERVAL)?
     } else {
-        //println!("{}", output);
+        hash_path(file_path, cache)?
+    };
+    Ok(Contents {
+        checksums,
+        extra,
+        acquisition,
+    })
+}
+
+/// Stores checksums in extended attributes, returning whether they were stored
+/// Nothing is stored if the file was modified while it was being hashed,
+/// since the digests may then not match the recorded modification time
+fn store_xattrs(
+    file_path: &Path,
+    checksums: &Checksums,
+    mtime: std::time::SystemTime,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if fs::metadata(file_path)?.modified()? != mtime {
+        eprintln!(
+            "Warning: '{}' was modified while hashing, extended attributes not stored",
+            file_path.display()
+        );
+        return Ok(false);
+    }
+    xattrs::store(file_path, checksums, mtime)
+        .map_err(|e| format!("Cannot store extended attributes: {}", e))?;
+    Ok(true)
+}
+
+/// Formats the result block printed for each file
+/// When only a byte range was hashed, its offset is given and the size is its length
+fn format_output(
+    file_path: &Path,
+    checksums: &Checksums,
+    details: &[String],
+    start_time: Instant,
+) -> String {
+    let details: String = details.iter().map(|line| format!("\n{}", line)).collect();
+    format!(
+        "File: {}{}\nSize:    {}\nCRC32:   {}\nMD5:   {}\nSHA1:  {}\nSHA256: {}\nSHA512: {}\nTime:  {:.2?}",
+        file_path.display(),
+        details,
+        format_size(checksums.size),
+        checksums.crc32,
+        checksums.md5,
+        checksums.sha1,
+        checksums.sha256,
+        checksums.sha512,
+        start_time.elapsed()
+    )
+}
+
+/// Describes devices, FIFOs and sockets for the Type line; None for regular files
+/// The Size line always counts the bytes actually hashed: a block device that
+/// provided fewer bytes than its reported size gets a warning
+fn describe_special(
+    file_path: &Path,
+    checksums: &Checksums,
+    range: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some(kind) = hasher::special_kind(&fs::metadata(file_path)?) else {
+        return Ok(None);
+    };
+    if kind != "block device" {
+        return Ok(Some(kind.to_string()));
     }
 
+    let size = hasher::expected_size(&fs::File::open(file_path)?)?.unwrap_or(0);
+    if !range && checksums.size != size {
+        eprintln!(
+            "Warning: '{}' reports {} bytes but {} were read",
+            file_path.display(),
+            size,
+            checksums.size
+        );
+    }
+    Ok(Some(format!("{} of {}", kind, format_size(size))))
+}
+
+/// Formats the --porcelain lines of a file: algorithm, digest, size in bytes
+/// and path, separated by tabs (tabs, newlines and backslashes in the path
+/// are escaped as \t, \n and \\)
+/// This format is a stable interface that does not change between versions
+fn format_porcelain(file_path: &Path, checksums: &Checksums, algorithms: &[Algorithm]) -> String {
+    let path = cache::escape(&file_path.to_string_lossy());
+    algorithms
+        .iter()
+        .map(|algo| {
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                algo.name(),
+                checksums.get(*algo),
+                checksums.size,
+                path
+            )
+        })
+        .collect()
+}
+
+/// Formats an additional result block (archive member, compressed file)
+fn format_entry(label: &str, name: &str, checksums: &Checksums) -> String {
+    format!(
+        "{}: {}\nSize:    {}\nCRC32:   {}\nMD5:   {}\nSHA1:  {}\nSHA256: {}\nSHA512: {}",
+        label,
+        name,
+        format_size(checksums.size),
+        checksums.crc32,
+        checksums.md5,
+        checksums.sha1,
+        checksums.sha256,
+        checksums.sha512
+    )
+}
+
+/// Hashes every input (directories are walked recursively) and writes the manifests
+/// With `quiet`, nothing is printed; `bytes_done` counts the bytes of the files
+/// hashed so far, for the summary of an interrupted run
+fn run_manifest(
+    options: &ManifestOptions,
+    inputs: &[PathBuf],
+    mut cache: Option<&mut HashCache>,
+    quiet: bool,
+    bytes_done: &mut u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start_time = Instant::now();
+
+    // Never hash the manifests being written
+    let excluded: Vec<PathBuf> = options
+        .targets()
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    let files = collect_inputs(
+        inputs,
+        options.base.as_deref(),
+        options.prefix.as_deref(),
+        &excluded,
+    )?;
+
+    let mut entries = Vec::with_capacity(files.len());
+    for (entry_path, file) in files {
+        let checksums = hash_path(&file, cache.as_deref_mut())?;
+        *bytes_done += checksums.size;
+        entries.push((entry_path, checksums));
+    }
+
+    let written = write_manifests(options, &entries)?;
+    if quiet {
+        return Ok(());
+    }
+    for path in written {
+        println!("Manifest written to: {}", path.display());
+    }
+    println!("Files: {}", entries.len());
+    if let Some(cache) = cache {
+        println!("Cached: {}", cache.hits);
+    }
+    println!("Time:  {:.2?}", start_time.elapsed());
+
     Ok(())
 }
 
 /// Expands tilde (~) to home directory in file paths
 /// Handles both "~" and "~/path" patterns
-/// Supports snap confinement (SNAP_REAL_HOME), standard Unix (HOME), and Windows (USERPROFILE)
 fn expand_tilde(path: &str) -> PathBuf {
-    if path.starts_with("~/") || path == "~" {
-        // Priority: SNAP_REAL_HOME (snap), HOME (Unix), USERPROFILE (Windows)
-        let home = env::var_os("SNAP_REAL_HOME")
-            .or_else(|| env::var_os("HOME"))
-            .or_else(|| env::var_os("USERPROFILE"));
-
-        if let Some(home_dir) = home {
-            if path == "~" {
-                return PathBuf::from(home_dir);
-            } else {
-                return PathBuf::from(home_dir).join(&path[2..]);
-            }
+    if (path.starts_with("~/") || path == "~")
+        && let Some(home_dir) = home_dir()
+    {
+        if path == "~" {
+            return home_dir;
+        } else {
+            return home_dir.join(&path[2..]);
         }
     }
     PathBuf::from(path)
 }
 
+/// Value parser for paths given on the command line, with tilde expansion
+fn parse_path(value: &str) -> Result<PathBuf, String> {
+    Ok(expand_tilde(value))
+}
+
+/// Returns the user's real home directory
+/// Supports snap confinement (SNAP_REAL_HOME), standard Unix (HOME), and Windows (USERPROFILE)
+fn home_dir() -> Option<PathBuf> {
+    // Priority: SNAP_REAL_HOME (snap), HOME (Unix), USERPROFILE (Windows)
+    env::var_os("SNAP_REAL_HOME")
+        .or_else(|| env::var_os("HOME"))
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
 /// Converts byte count to human-readable format
 /// Example: 1024 → "1 KB (1024 bytes)"
 fn format_size(size_bytes: u64) -> String {
@@ -310,69 +1233,47 @@ fn format_size(size_bytes: u64) -> String {
     }
 }
 
-/// Displays help information
-fn print_help() {
-    println!(
-        r#"
-Slashsum - Calculate multiple checksums simultaneously
-
-USAGE:
-    slashsum <FILE> [OPTIONS]
-
-OPTIONS:
-    --save       Save checksums to a .checksum file
-    -h, --help   Print help information
-    --version    Print version information
-
-EXAMPLES:
-    slashsum file.txt            # Calculate and display checksums
-    slashsum file.txt --save     # Save results to file.txt.checksum
-    slashsum --version           # Display version and license information
-    slashsum -h                  # Show this help message"#
-    );
-}
-
-/// Displays version and full MIT license
-fn print_version_and_license() {
-    println!(
-        r#"MIT License
-
-Copyright (c) 2025-2026 Nicolas DEOUX
-                   NDXDev@gmail.com
-
-Permission is hereby granted, free of charge, to any person obtaining a copy
-of this software and associated documentation files (the "Software"), to deal
-in the Software without restriction, including without limitation the rights
-to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
-copies of the Software, and to permit persons to whom the Software is
-furnished to do so, subject to the following conditions:
-
-The above copyright notice and this permission notice shall be included in all
-copies or substantial portions of the Software.
-
-THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
-IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
-FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
-AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
-LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
-OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
-SOFTWARE."#
-    );
+/// Parses a byte count with an optional size suffix
+/// K, M, G and T (or KiB, MiB, ...) are powers of 1024; KB, MB, GB and TB are powers of 1000
+/// Example: "4K" → 4096, "1MB" → 1000000
+fn parse_size(value: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size '{}' (examples: 512, 4K, 1MiB, 2GB)", value);
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (number, suffix) = trimmed.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+
+    let multiplier: u64 = match suffix.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KIB" => 1 << 10,
+        "M" | "MIB" => 1 << 20,
+        "G" | "GIB" => 1 << 30,
+        "T" | "TIB" => 1 << 40,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(multiplier).ok_or_else(invalid)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossbeam_channel::bounded;
-    use md5::Context;
-
-    use sha1::{Digest, Sha1};
-    use sha2::{Sha256, Sha512};
     use std::fs::File;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
-    use std::time::Instant;
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_args(args: &[String]) -> Result<Options, String> {
+        cli::try_parse::<HashArgs>(args)?.into_options(&Config::default())
+    }
 
     #[test]
     fn test_format_size() {
@@ -382,56 +1283,6 @@ mod tests {
         assert_eq!(format_size(3_145_728), "3 MB (3145728 bytes)");
     }
 
-    #[test]
-    fn test_compute_hash_md5() {
-        // Test MD5 with known "abc" input
-        let (tx, rx) = bounded(2);
-
-        tx.send(Arc::from([0x61u8, 0x62, 0x63])).unwrap(); // "abc"
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Context::new(),                            // initialize MD5 context
-            |context, data| context.consume(data),        // update with data
-            |context| format!("{:x}", context.compute()), // finalize and format
-        );
-
-        assert_eq!(result, "900150983cd24fb0d6963f7d28e17f72");
-    }
-
-    #[test]
-    fn test_compute_hash_crc32() {
-        // Test CRC32 with "Hello world!" input
-        let (tx, rx) = bounded(2);
-
-        tx.send(Arc::from(b"Hello world!".as_ref())).unwrap();
-        drop(tx);
-
-        // Use the same structure adopted for CRC32
-        let result = compute_hash(
-            rx,
-            || {
-                // Structure for CRC32 calculation
-                struct Crc32Calculator {
-                    crc_algo: Crc<u32>,
-                    data: Vec<u8>,
-                }
-
-                Crc32Calculator {
-                    crc_algo: Crc::<u32>::new(&crc::CRC_32_ISO_HDLC),
-                    data: Vec::new(),
-                }
-            },
-            |calculator, data| {
-                calculator.data.extend_from_slice(data);
-            },
-            |calculator| format!("{:08x}", calculator.crc_algo.checksum(&calculator.data)),
-        );
-
-        assert_eq!(result, "1b851995");
-    }
-
     #[test]
     fn test_file_not_found() {
         // Verify error handling for missing files
@@ -455,228 +1306,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_compute_hash_sha1() {
-        // Test SHA1 with "abc"
-        let (tx, rx) = bounded(2);
-        tx.send(Arc::from(b"abc".as_ref())).unwrap();
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Sha1::new(),
-            |digest, data| {
-                digest.update(data);
-            },
-            |digest| format!("{:x}", digest.finalize()),
-        );
-
-        assert_eq!(result, "a9993e364706816aba3e25717850c26c9cd0d89d");
-    }
-
-    #[test]
-    fn test_compute_hash_sha256() {
-        // Test SHA256 with "abc"
-        let (tx, rx) = bounded(2);
-        tx.send(Arc::from(b"abc".as_ref())).unwrap();
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Sha256::new(),
-            |digest, data| {
-                digest.update(data);
-            },
-            |digest| format!("{:x}", digest.finalize()),
-        );
-
-        assert_eq!(
-            result,
-            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
-        );
-    }
-
-    #[test]
-    fn test_compute_hash_sha512() {
-        // Test SHA512 with "abc"
-        let (tx, rx) = bounded(2);
-        tx.send(Arc::from(b"abc".as_ref())).unwrap();
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Sha512::new(),
-            |digest, data| {
-                digest.update(data);
-            },
-            |digest| format!("{:x}", digest.finalize()),
-        );
-
-        assert_eq!(
-            result,
-            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
-        );
-    }
-
-    #[test]
-    fn test_compute_hash_empty_input() {
-        // Test with empty input
-        let (tx, rx) = bounded(1);
-        drop(tx); // Close channel immediately
-
-        let result = compute_hash(
-            rx,
-            || Context::new(),
-            |context, data| context.consume(data),
-            |context| format!("{:x}", context.compute()),
-        );
-
-        // MD5 of empty string
-        assert_eq!(result, "d41d8cd98f00b204e9800998ecf8427e");
-    }
-
-    #[test]
-    fn test_compute_hash_multiple_chunks() {
-        // Test with multiple chunks
-        let (tx, rx) = bounded(5);
-
-        // Send "Hello" then " World!" to form "Hello World!"
-        tx.send(Arc::from(b"Hello".as_ref())).unwrap();
-        tx.send(Arc::from(b" World!".as_ref())).unwrap();
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Context::new(),
-            |context, data| context.consume(data),
-            |context| format!("{:x}", context.compute()),
-        );
-
-        // MD5 of "Hello World!"
-        assert_eq!(result, "ed076287532e86365e841e92bfc50d8c");
-    }
-
-    #[test]
-    fn test_crc32_calculator() {
-        // Direct test of Crc32Calculator structure
-        let mut calculator = Crc32Calculator::new();
-        calculator.update(b"Hello");
-        calculator.update(b" World!");
-        let result = calculator.finalize();
-
-        // CRC32 of "Hello World!" - corrected value
-        assert_eq!(result, 472456355); // 0x1c291ca3 in decimal
-    }
-
-    #[test]
-    fn test_crc32_calculator_empty() {
-        // Test CRC32 with empty data
-        let calculator = Crc32Calculator::new();
-        let result = calculator.finalize();
-
-        // CRC32 of empty string
-        assert_eq!(result, 0x00000000);
-    }
-
-    #[test]
-    fn test_large_chunk_processing() {
-        // Test with large chunk (similar to 1MB buffer size)
-        let large_data = vec![0x42u8; 1_048_576]; // 1MB of data
-        let (tx, rx) = bounded(2);
-
-        tx.send(Arc::from(large_data.into_boxed_slice())).unwrap();
-        drop(tx);
-
-        let result = compute_hash(
-            rx,
-            || Context::new(),
-            |context, data| context.consume(data),
-            |context| format!("{:x}", context.compute()),
-        );
-
-        // This test verifies that large chunk processing works
-        assert_eq!(result.len(), 32); // MD5 always produces 32 hex characters
-    }
-
-    #[test]
-    fn test_channel_capacity() {
-        // Test with limited channel capacity
-        let (tx, rx) = bounded(1); // Very small capacity
-
-        // Use a thread to read data while sending
-        let handle = std::thread::spawn(move || {
-            compute_hash(
-                rx,
-                || Context::new(),
-                |context, data| context.consume(data),
-                |context| format!("{:x}", context.compute()),
-            )
-        });
-
-        // Send multiple chunks quickly
-        for i in 0..10 {
-            let data = format!("chunk{}", i);
-            tx.send(Arc::from(data.as_bytes())).unwrap();
-        }
-        drop(tx); // Close the channel
-
-        // Wait for result
-        let result = handle.join().unwrap();
-
-        // Verify all chunks were processed
-        assert_eq!(result.len(), 32);
-    }
-
-    #[test]
-    fn test_concurrent_hash_computation() {
-        // Test simulating real concurrent behavior
-        use std::thread;
-
-        let test_data = b"This is a test for concurrent hash computation";
-        let chunk = Arc::from(test_data.as_ref());
-
-        // Create multiple channels like in the main code
-        let (md5_tx, md5_rx) = bounded(1024);
-        let (sha1_tx, sha1_rx) = bounded(1024);
-
-        // Launch computation threads
-        let md5_handle = thread::spawn(move || {
-            compute_hash(
-                md5_rx,
-                || Context::new(),
-                |context, data| context.consume(data),
-                |context| format!("{:x}", context.compute()),
-            )
-        });
-
-        let sha1_handle = thread::spawn(move || {
-            compute_hash(
-                sha1_rx,
-                || Sha1::new(),
-                |digest, data| {
-                    digest.update(data);
-                },
-                |digest| format!("{:x}", digest.finalize()),
-            )
-        });
-
-        // Send data
-        md5_tx.send(Arc::clone(&chunk)).unwrap();
-        sha1_tx.send(chunk).unwrap();
-
-        // Close channels
-        drop(md5_tx);
-        drop(sha1_tx);
-
-        // Retrieve results
-        let md5_result = md5_handle.join().unwrap();
-        let sha1_result = sha1_handle.join().unwrap();
-
-        // Verify results - corrected with actual computed value
-        assert_eq!(md5_result, "f801c3cb79c641ab70efc5b525af573c");
-        assert_eq!(sha1_result.len(), 40); // SHA1 produces 40 hex characters
-    }
-
     #[test]
     fn test_format_size_precision() {
         // Precision test for format_size
@@ -703,32 +1332,6 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    #[ignore] // Ignored by default, run with cargo test -- --ignored
-    fn benchmark_hash_computation() {
-        let large_data = vec![0x42u8; 10_000_000]; // 10MB of data
-        let chunk = Arc::from(large_data.into_boxed_slice());
-
-        let start = Instant::now();
-
-        let (tx, rx) = bounded(1);
-        tx.send(chunk).unwrap();
-        drop(tx);
-
-        let _result = compute_hash(
-            rx,
-            || Context::new(),
-            |context, data| context.consume(data),
-            |context| format!("{:x}", context.compute()),
-        );
-
-        let duration = start.elapsed();
-        println!("Hash computation took: {:?}", duration);
-
-        // Verify it takes less than 1 second (adjust as needed)
-        assert!(duration.as_secs() < 1);
-    }
-
     #[test]
     fn test_expand_tilde() {
         use super::expand_tilde;
@@ -756,4 +1359,215 @@ mod tests {
         let path = expand_tilde("~user/file.txt");
         assert_eq!(path, PathBuf::from("~user/file.txt"));
     }
+
+    #[test]
+    fn test_parse_args() {
+        // Options are accepted before or after files
+        let options = parse_args(&args(&["--save", "a.txt", "b.txt"])).unwrap();
+        assert_eq!(options.files, vec!["a.txt", "b.txt"]);
+        assert!(options.save);
+        assert!(options.manifest.is_none());
+
+        let options = parse_args(&args(&[
+            "dist",
+            "--manifest",
+            "SHA256SUMS",
+            "--base",
+            "dist",
+            "--algo",
+            "sha256",
+        ]))
+        .unwrap();
+        let manifest = options.manifest.unwrap();
+        assert_eq!(manifest.path, PathBuf::from("SHA256SUMS"));
+        assert_eq!(manifest.base, Some(PathBuf::from("dist")));
+        assert_eq!(manifest.algorithms, vec![hasher::Algorithm::Sha256]);
+
+        let options = parse_args(&args(&["vm.img", "--lock", "--retry", "2"])).unwrap();
+        assert!(options.lock);
+        assert_eq!(options.retry, 2);
+    }
+
+    #[test]
+    fn test_parse_args_cache() {
+        assert!(!parse_args(&args(&["file"])).unwrap().cache);
+        assert!(parse_args(&args(&["file", "--cache"])).unwrap().cache);
+
+        let options = parse_args(&args(&["file", "--rehash"])).unwrap();
+        assert!(options.cache && options.rehash);
+
+        // --no-cache wins, wherever it appears
+        assert!(
+            !parse_args(&args(&["--no-cache", "file", "--cache"]))
+                .unwrap()
+                .cache
+        );
+    }
+
+    #[test]
+    fn test_parse_args_config_defaults() {
+        let mut config = Config::default();
+        (config.save, config.cache, config.lock) = (true, true, true);
+        let parse = |list: &[&str]| {
+            cli::try_parse::<HashArgs>(&args(list))
+                .unwrap()
+                .into_options(&config)
+                .unwrap()
+        };
+
+        let options = parse(&["file"]);
+        assert!(options.save && options.cache && options.lock);
+
+        // Each configured default can be turned off for one run
+        let options = parse(&["file", "--no-save", "--no-cache", "--no-lock"]);
+        assert!(!options.save && !options.cache && !options.lock);
+        assert!(!parse(&["file", "--save", "--no-save"]).save);
+    }
+
+    #[test]
+    fn test_parse_args_config_output() {
+        let parse = |configured: Output, list: &[&str]| {
+            let mut config = Config::default();
+            config.output = Some(configured);
+            cli::try_parse::<HashArgs>(&args(list))
+                .unwrap()
+                .into_options(&config)
+                .map(|options| options.output)
+        };
+
+        assert_eq!(parse(Output::Quiet, &["file"]), Ok(Output::Quiet));
+        assert_eq!(
+            parse(Output::Quiet, &["file", "--porcelain"]),
+            Ok(Output::Porcelain)
+        );
+        assert_eq!(parse(Output::Porcelain, &["file", "-q"]), Ok(Output::Quiet));
+        // Configured quiet output allows --algo, porcelain is left out of manifest runs
+        assert!(parse(Output::Quiet, &["file", "-a", "sha256"]).is_ok());
+        assert_eq!(
+            parse(Output::Porcelain, &["dir", "--manifest", "SUMS"]),
+            Ok(Output::Normal)
+        );
+    }
+
+    #[test]
+    fn test_parse_args_config_algorithms_hashdeep() {
+        let parse = |configured: &str, list: &[&str]| {
+            let mut config = Config::default();
+            config.algorithms = Some(configured.to_string());
+            cli::try_parse::<HashArgs>(&args(list))
+                .unwrap()
+                .into_options(&config)
+                .map(|options| options.manifest.unwrap().algorithms)
+        };
+
+        // Configured algorithms hashdeep lacks are left out, --algo is checked strictly
+        let hashdeep = ["dir", "--manifest", "out", "--format", "hashdeep"];
+        assert_eq!(parse("sha1,sha512", &hashdeep), Ok(vec![Algorithm::Sha1]));
+        assert_eq!(parse("sha512", &hashdeep), Ok(hashdeep::DEFAULT.to_vec()));
+        assert!(parse("sha1", &[&hashdeep[..], &["--algo", "sha512"]].concat()).is_err());
+        assert_eq!(
+            parse("sha1,sha512", &["dir", "--manifest", "out"]),
+            Ok(vec![Algorithm::Sha1, Algorithm::Sha512])
+        );
+    }
+
+    #[test]
+    fn test_parse_args_output() {
+        let options = parse_args(&args(&["file"])).unwrap();
+        assert_eq!(options.output, Output::Normal);
+        assert_eq!(options.algorithms, Algorithm::ALL.to_vec());
+
+        let options = parse_args(&args(&["-q", "-a", "sha256,md5", "file"])).unwrap();
+        assert_eq!(options.output, Output::Quiet);
+        assert_eq!(options.algorithms, vec![Algorithm::Md5, Algorithm::Sha256]);
+        let options = parse_args(&args(&["file", "--porcelain"])).unwrap();
+        assert_eq!(options.output, Output::Porcelain);
+
+        assert!(parse_args(&args(&["file", "-a", "sha256"])).is_err());
+        assert!(parse_args(&args(&["file", "-q", "--porcelain"])).is_err());
+        assert!(parse_args(&args(&["dir", "--manifest", "SUMS", "--porcelain"])).is_err());
+        assert!(parse_args(&args(&["dir", "--manifest", "SUMS", "-q"])).is_ok());
+    }
+
+    #[test]
+    fn test_format_porcelain() {
+        let checksums = hasher::hash_reader(&b"abc"[..]).unwrap();
+        let lines = format_porcelain(
+            Path::new("dir/a\tb.txt"),
+            &checksums,
+            &[Algorithm::Md5, Algorithm::Sha256],
+        );
+        assert_eq!(
+            lines,
+            "MD5\t900150983cd24fb0d6963f7d28e17f72\t3\tdir/a\\tb.txt\n\
+             SHA256\tba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\t3\tdir/a\\tb.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["file", "--bogus"])).is_err());
+        assert!(parse_args(&args(&["file", "--manifest"])).is_err());
+        assert!(parse_args(&args(&["file", "--base", "dir"])).is_err());
+        assert!(parse_args(&args(&["file", "--manifest", "x", "--format", "csv"])).is_err());
+        assert!(
+            parse_args(&args(&[
+                "file",
+                "--manifest",
+                "x",
+                "--format",
+                "hashdeep",
+                "--algo",
+                "sha512"
+            ]))
+            .is_err()
+        );
+        assert!(parse_args(&args(&["file", "--save", "--manifest", "SUMS"])).is_err());
+        assert!(parse_args(&args(&["file", "--expect", "xyz"])).is_err());
+        assert!(parse_args(&args(&["file", "--expect", "abcd"])).is_err());
+        assert!(parse_args(&args(&["dir", "--manifest", "SUMS", "--xattr-store"])).is_err());
+        assert!(parse_args(&args(&["file", "--xattr-verify", "--cache"])).is_err());
+        assert!(parse_args(&args(&["file", "--both"])).is_err());
+        assert!(parse_args(&args(&["file", "--decompress", "--archive"])).is_err());
+        assert!(parse_args(&args(&["file", "--retry", "-1"])).is_err());
+        assert!(parse_args(&args(&["dir", "--manifest", "SUMS", "--lock"])).is_err());
+        assert!(parse_args(&args(&["file", "--report", "pdf"])).is_err());
+        assert!(parse_args(&args(&["file", "--report-file", "r.xml"])).is_err());
+        assert!(parse_args(&args(&["dir", "--manifest", "SUMS", "--report", "text"])).is_err());
+        assert!(parse_args(&args(&["file", "--xattr-verify", "--cache", "--no-cache"])).is_ok());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("1MiB"), Ok(1_048_576));
+        assert_eq!(parse_size("2gb"), Ok(2_000_000_000));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("1.5M").is_err());
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("99999999T").is_err());
+
+        let options = parse_args(&args(&["disk.img", "--offset", "1M", "--length", "512"]));
+        let options = options.unwrap();
+        assert_eq!(
+            (options.offset, options.length),
+            (Some(1_048_576), Some(512))
+        );
+        assert!(parse_args(&args(&["dir", "--offset", "1", "--manifest", "SUMS"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_expected() {
+        let options = parse_args(&args(&[
+            "file",
+            "--expect",
+            " 900150983CD24FB0D6963F7D28E17F72 ",
+        ]));
+        assert_eq!(
+            options.unwrap().expect.as_deref(),
+            Some("900150983cd24fb0d6963f7d28e17f72")
+        );
+    }
 }
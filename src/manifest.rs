@@ -0,0 +1,490 @@
+// Import standard library components
+use std::{
+    collections::BTreeMap,            // Sorted map for deterministic ordering
+    error::Error,                     // Error trait for boxed errors
+    fs,                               // File handling
+    io,                               // Symlink resolution
+    path::{Component, Path, PathBuf}, // Path manipulation
+};
+
+use crate::hasher::{Algorithm, Checksums};
+use crate::{atomic, hashdeep, walk};
+
+/// Layout of a manifest file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Sums,     // coreutils "<hex>  <path>" or tagged "ALGO (<path>) = <hex>" lines
+    Hashdeep, // hashdeep CSV: size,hashes...,filename
+}
+
+impl ManifestFormat {
+    /// Parses a --format value
+    pub fn from_name(name: &str) -> Result<ManifestFormat, String> {
+        match name {
+            "sums" => Ok(ManifestFormat::Sums),
+            "hashdeep" => Ok(ManifestFormat::Hashdeep),
+            _ => Err(format!(
+                "Unknown manifest format '{}' (use sums or hashdeep)",
+                name
+            )),
+        }
+    }
+}
+
+/// Settings for aggregate manifest generation (--manifest)
+pub struct ManifestOptions {
+    pub path: PathBuf, // Manifest file, or directory when split
+    pub format: ManifestFormat,
+    pub base: Option<PathBuf>, // Paths are written relative to this directory
+    pub prefix: Option<String>, // Prepended to every written path
+    pub algorithms: Vec<Algorithm>,
+    pub split: bool,  // One <ALGO>SUMS file per algorithm
+    pub append: bool, // Merge with existing entries instead of replacing
+}
+
+impl ManifestOptions {
+    /// Manifest files written by these options, paired with their algorithms
+    pub fn targets(&self) -> Vec<(PathBuf, Vec<Algorithm>)> {
+        if self.split {
+            self.algorithms
+                .iter()
+                .map(|algo| (self.path.join(format!("{}SUMS", algo.name())), vec![*algo]))
+                .collect()
+        } else {
+            vec![(self.path.clone(), self.algorithms.clone())]
+        }
+    }
+}
+
+/// Resolves the directory holding a file but keeps the file's own name, so a
+/// symbolic link is listed under its name, not under the path it points to
+fn canonical_location(file: &Path) -> io::Result<PathBuf> {
+    match (file.parent(), file.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            Ok(parent.canonicalize()?.join(name))
+        }
+        _ => file.canonicalize(),
+    }
+}
+
+/// Converts a file path to the form written in the manifest
+/// The path is made relative to `base` (if given), uses '/' separators
+/// and gets `prefix` prepended
+pub fn manifest_path(
+    file: &Path,
+    base: Option<&Path>,
+    prefix: Option<&str>,
+) -> Result<String, String> {
+    let relative = match base {
+        Some(base) => {
+            let canonical_base = base
+                .canonicalize()
+                .map_err(|e| format!("Base directory '{}': {}", base.display(), e))?;
+            let canonical_file =
+                canonical_location(file).map_err(|e| format!("'{}': {}", file.display(), e))?;
+            canonical_file
+                .strip_prefix(&canonical_base)
+                .map_err(|_| {
+                    format!(
+                        "'{}' is not inside base directory '{}'",
+                        file.display(),
+                        base.display()
+                    )
+                })?
+                .to_path_buf()
+        }
+        None => file.to_path_buf(),
+    };
+
+    // Join components with '/' so manifests are identical across platforms
+    let path = relative
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| match c {
+            Component::RootDir => String::new(),
+            other => other.as_os_str().to_string_lossy().into_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    Ok(match prefix {
+        Some(prefix) if !prefix.is_empty() => {
+            format!("{}/{}", prefix.trim_end_matches('/'), path)
+        }
+        _ => path,
+    })
+}
+
+/// Lists the files below the inputs, keyed by the path written in the manifest
+/// Files in `excluded` (e.g. the manifests themselves) are skipped; the map keeps
+/// ordering deterministic
+pub fn collect_inputs(
+    inputs: &[PathBuf],
+    base: Option<&Path>,
+    prefix: Option<&str>,
+    excluded: &[PathBuf],
+) -> Result<BTreeMap<String, PathBuf>, Box<dyn Error>> {
+    let excluded: Vec<PathBuf> = excluded
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+
+    let mut files = BTreeMap::new();
+    for input in inputs {
+        for file in walk::collect_files(input)? {
+            if file
+                .canonicalize()
+                .is_ok_and(|canonical| excluded.contains(&canonical))
+            {
+                continue;
+            }
+            files
+                .entry(manifest_path(&file, base, prefix)?)
+                .or_insert(file);
+        }
+    }
+    Ok(files)
+}
+
+/// Formats one manifest line, escaping paths like coreutils does
+/// - Untagged (GNU): "<hex>  <path>"
+/// - Tagged (BSD):   "<ALGO> (<path>) = <hex>"
+fn format_line(algo: Option<Algorithm>, path: &str, digest: &str) -> String {
+    let needs_escape = path.contains(['\\', '\n', '\r']);
+    let escaped = if needs_escape {
+        path.replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    } else {
+        path.to_string()
+    };
+    let marker = if needs_escape { "\\" } else { "" };
+
+    match algo {
+        Some(algo) => format!("{}{} ({}) = {}", marker, algo.name(), escaped, digest),
+        None => format!("{}{}  {}", marker, digest, escaped),
+    }
+}
+
+/// Reverses the escaping done by `format_line`
+fn unescape_path(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Parses one manifest line in either tagged or untagged format
+/// Returns the algorithm (inferred from the digest length when untagged),
+/// the path and the digest
+pub fn parse_line(line: &str) -> Option<(Algorithm, String, String)> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let decode = |path: &str| {
+        if escaped {
+            unescape_path(path)
+        } else {
+            path.to_string()
+        }
+    };
+
+    // Tagged format: "ALGO (path) = digest"
+    if let Some((name, rest)) = line.split_once(" (")
+        && let Some(algo) = Algorithm::from_name(name)
+    {
+        let (path, digest) = rest.rsplit_once(") = ")?;
+        return is_digest(digest, algo).then(|| (algo, decode(path), digest.to_lowercase()));
+    }
+
+    // Untagged format: "digest  path" (text) or "digest *path" (binary)
+    let (digest, rest) = line.split_once(' ')?;
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    let algo = Algorithm::ALL
+        .into_iter()
+        .find(|algo| is_digest(digest, *algo))?;
+    Some((algo, decode(path), digest.to_lowercase()))
+}
+
+/// Checks that a string is a hexadecimal digest of the right length for an algorithm
+fn is_digest(digest: &str, algo: Algorithm) -> bool {
+    digest.len() == algo.hex_len() && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Merges new entries into the lines of an existing sums manifest (if any)
+/// and renders the result sorted by path
+fn merge_sums(
+    existing: Option<&str>,
+    algorithms: &[Algorithm],
+    entries: &[(String, Checksums)],
+) -> Result<String, String> {
+    // Records keyed by (path, algorithm) so output order never depends on input order
+    let mut records: BTreeMap<(String, Algorithm), String> = BTreeMap::new();
+
+    if let Some(content) = existing {
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (algo, entry_path, digest) =
+                parse_line(line).ok_or(format!("Line {}: invalid manifest line", number + 1))?;
+            records.insert((entry_path, algo), digest);
+        }
+    }
+
+    for (entry_path, checksums) in entries {
+        for algo in algorithms {
+            records.insert(
+                (entry_path.clone(), *algo),
+                checksums.get(*algo).to_string(),
+            );
+        }
+    }
+
+    // Untagged lines are only unambiguous when a single algorithm is present
+    let first_algo = records.keys().next().map(|(_, algo)| *algo);
+    let tagged = records.keys().any(|(_, algo)| Some(*algo) != first_algo);
+
+    let mut output = String::new();
+    for ((entry_path, algo), digest) in &records {
+        output.push_str(&format_line(tagged.then_some(*algo), entry_path, digest));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Writes (or merges into) one manifest file while holding an exclusive lock
+/// The lock serializes concurrent runs that append to the same manifest, and
+/// the new content replaces the old one atomically
+fn update_manifest(
+    path: &Path,
+    format: ManifestFormat,
+    algorithms: &[Algorithm],
+    entries: &[(String, Checksums)],
+    append: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (lock, content) = atomic::lock(path)?;
+    let existing = append.then_some(content.as_str());
+
+    let output = match format {
+        ManifestFormat::Sums => merge_sums(existing, algorithms, entries),
+        ManifestFormat::Hashdeep => hashdeep::merge(existing, algorithms, entries),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    atomic::replace(path, output.as_bytes())?;
+    drop(lock);
+    Ok(())
+}
+
+/// Writes all manifests for a set of hashed files
+/// Returns the paths of the written manifest files
+pub fn write_manifests(
+    options: &ManifestOptions,
+    entries: &[(String, Checksums)],
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if options.split {
+        fs::create_dir_all(&options.path)?;
+    }
+
+    let mut written = Vec::new();
+    for (path, algorithms) in options.targets() {
+        update_manifest(&path, options.format, &algorithms, entries, options.append)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::hash_reader;
+    use tempfile::tempdir;
+
+    fn options(path: PathBuf, algorithms: Vec<Algorithm>) -> ManifestOptions {
+        ManifestOptions {
+            path,
+            format: ManifestFormat::Sums,
+            base: None,
+            prefix: None,
+            algorithms,
+            split: false,
+            append: false,
+        }
+    }
+
+    #[test]
+    fn test_manifest_path_relative_to_base() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("dist")).unwrap();
+        let file = dir.path().join("dist").join("app.tar.gz");
+        fs::write(&file, b"x").unwrap();
+
+        assert_eq!(
+            manifest_path(&file, Some(dir.path()), None).unwrap(),
+            "dist/app.tar.gz"
+        );
+        assert_eq!(
+            manifest_path(&file, Some(dir.path()), Some("v1.0/")).unwrap(),
+            "v1.0/dist/app.tar.gz"
+        );
+        assert!(manifest_path(dir.path(), Some(&file), None).is_err());
+        assert_eq!(
+            manifest_path(Path::new("./a/b.txt"), None, None).unwrap(),
+            "a/b.txt"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_files_keep_their_name() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let dist = dir.path().join("dist");
+        fs::create_dir(&dist).unwrap();
+        fs::write(dist.join("a"), b"a").unwrap();
+        fs::write(outside.path().join("b"), b"b").unwrap();
+        std::os::unix::fs::symlink(dist.join("a"), dist.join("alias")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("b"), dist.join("b")).unwrap();
+
+        assert_eq!(
+            manifest_path(&dist.join("b"), Some(dir.path()), None).unwrap(),
+            "dist/b"
+        );
+        let files = collect_inputs(&[dist], Some(dir.path()), None, &[]).unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec!["dist/a", "dist/alias", "dist/b"]
+        );
+    }
+
+    #[test]
+    fn test_format_and_parse_roundtrip() {
+        let sha = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let line = format_line(None, "dir/file.bin", sha);
+        assert_eq!(line, format!("{}  dir/file.bin", sha));
+        assert_eq!(
+            parse_line(&line),
+            Some((
+                Algorithm::Sha256,
+                "dir/file.bin".to_string(),
+                sha.to_string()
+            ))
+        );
+
+        let line = format_line(
+            Some(Algorithm::Md5),
+            "odd\nname",
+            "900150983cd24fb0d6963f7d28e17f72",
+        );
+        assert_eq!(
+            line,
+            "\\MD5 (odd\\nname) = 900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(parse_line(&line).unwrap().1, "odd\nname");
+
+        // Binary-mode marker and garbage
+        assert!(parse_line(&format!("{} *file", sha)).is_some());
+        assert_eq!(parse_line("not a manifest line"), None);
+    }
+
+    #[test]
+    fn test_write_single_algorithm_sorted() {
+        let dir = tempdir().unwrap();
+        let manifest = dir.path().join("SHA256SUMS");
+        let entries = vec![
+            ("b.txt".to_string(), hash_reader(&b"b"[..]).unwrap()),
+            ("a.txt".to_string(), hash_reader(&b"a"[..]).unwrap()),
+        ];
+
+        write_manifests(
+            &options(manifest.clone(), vec![Algorithm::Sha256]),
+            &entries,
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(&manifest).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("  a.txt"));
+        assert!(lines[1].ends_with("  b.txt"));
+    }
+
+    #[test]
+    fn test_append_merges_and_replaces() {
+        let dir = tempdir().unwrap();
+        let manifest = dir.path().join("SUMS");
+        let mut opts = options(manifest.clone(), vec![Algorithm::Md5]);
+
+        let first = vec![
+            ("a.txt".to_string(), hash_reader(&b"old"[..]).unwrap()),
+            ("c.txt".to_string(), hash_reader(&b"c"[..]).unwrap()),
+        ];
+        write_manifests(&opts, &first).unwrap();
+
+        opts.append = true;
+        let second = vec![
+            ("a.txt".to_string(), hash_reader(&b"new"[..]).unwrap()),
+            ("b.txt".to_string(), hash_reader(&b"b"[..]).unwrap()),
+        ];
+        write_manifests(&opts, &second).unwrap();
+
+        let content = fs::read_to_string(&manifest).unwrap();
+        let paths: Vec<String> = content.lines().map(|l| parse_line(l).unwrap().1).collect();
+        assert_eq!(paths, vec!["a.txt", "b.txt", "c.txt"]);
+        assert!(content.contains(&second[0].1.md5));
+        assert!(!content.contains(&first[0].1.md5));
+    }
+
+    #[test]
+    fn test_split_and_combined() {
+        let dir = tempdir().unwrap();
+        let entries = vec![("f".to_string(), hash_reader(&b"abc"[..]).unwrap())];
+
+        let mut opts = options(
+            dir.path().join("sums"),
+            vec![Algorithm::Md5, Algorithm::Sha1],
+        );
+        opts.split = true;
+        let written = write_manifests(&opts, &entries).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                dir.path().join("sums").join("MD5SUMS"),
+                dir.path().join("sums").join("SHA1SUMS"),
+            ]
+        );
+
+        let combined = dir.path().join("CHECKSUMS");
+        write_manifests(
+            &options(combined.clone(), vec![Algorithm::Md5, Algorithm::Sha1]),
+            &entries,
+        )
+        .unwrap();
+        let content = fs::read_to_string(combined).unwrap();
+        assert_eq!(
+            content,
+            "MD5 (f) = 900150983cd24fb0d6963f7d28e17f72\n\
+             SHA1 (f) = a9993e364706816aba3e25717850c26c9cd0d89d\n"
+        );
+    }
+}
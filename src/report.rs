@@ -0,0 +1,640 @@
+// Import standard library components
+use std::{
+    env,                            // User name, platform
+    fs,                             // Source metadata, report file
+    io,                             // Errors
+    path::{Path, PathBuf},          // Path manipulation
+    time::{SystemTime, UNIX_EPOCH}, // Acquisition timestamps
+};
+
+use crate::forensic::Acquisition;
+use crate::hasher::{Algorithm, Checksums, expected_size, special_kind};
+use crate::{BUILD_VERSION, GIT_COMMIT};
+
+/// Namespace of Digital Forensics XML documents
+const DFXML_NAMESPACE: &str = "http://www.forensicswiki.org/wiki/Category:Digital_Forensics_XML";
+
+/// Layout of an acquisition report (--report)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Dfxml, // Digital Forensics XML
+    Text,  // Chain-of-custody text with sign-off lines
+}
+
+impl ReportFormat {
+    /// Parses a --report value
+    pub fn from_name(name: &str) -> Result<ReportFormat, String> {
+        match name {
+            "dfxml" => Ok(ReportFormat::Dfxml),
+            "text" => Ok(ReportFormat::Text),
+            _ => Err(format!(
+                "Unknown report format '{}' (use dfxml or text)",
+                name
+            )),
+        }
+    }
+
+    /// Extension of the report file written when no --report-file is given
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Dfxml => "xml",
+            ReportFormat::Text => "txt",
+        }
+    }
+}
+
+/// Identity of a hashed file or device, taken when it was hashed
+pub struct Source {
+    pub path: PathBuf,      // Absolute path
+    pub kind: &'static str, // "regular file", "block device"...
+    pub size: Option<u64>,  // File length or device capacity
+    pub mtime: Option<SystemTime>,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl Source {
+    /// Reads the metadata of a file, and the identity of a block device
+    /// Called before hashing: FIFOs and character devices are never opened,
+    /// since a second reader would wait for a writer that is gone
+    pub fn identify(path: &Path) -> io::Result<Source> {
+        let metadata = fs::metadata(path)?;
+        let (vendor, model, serial) = device_identity(&metadata);
+        let kind = special_kind(&metadata);
+        let size = match kind {
+            Some("block device") => expected_size(&fs::File::open(path)?)?,
+            Some(_) => None,
+            None => (metadata.len() > 0).then_some(metadata.len()),
+        };
+        Ok(Source {
+            path: fs::canonicalize(path)?,
+            kind: kind.unwrap_or("regular file"),
+            size,
+            mtime: metadata
+                .is_file()
+                .then(|| metadata.modified())
+                .transpose()?,
+            vendor,
+            model,
+            serial,
+        })
+    }
+}
+
+/// One hashed input of the report
+pub struct Entry {
+    pub source: Source,
+    pub checksums: Checksums,
+    pub changed: bool,                    // Modified while being hashed
+    pub acquisition: Option<Acquisition>, // Segments and bad blocks (--forensic)
+}
+
+/// Acquisition report of a run: who hashed what, where, when and with which tool
+pub struct Report {
+    pub format: ReportFormat,
+    pub start: SystemTime,
+    pub command_line: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Report {
+    /// Starts a report; the run is considered started now
+    pub fn new(format: ReportFormat, args: &[String]) -> Report {
+        Report {
+            format,
+            start: SystemTime::now(),
+            command_line: args.join(" "),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Records a hashed input, identified before it was hashed
+    pub fn add(
+        &mut self,
+        source: Source,
+        checksums: Checksums,
+        changed: bool,
+        acquisition: Option<Acquisition>,
+    ) {
+        self.entries.push(Entry {
+            source,
+            checksums,
+            changed,
+            acquisition,
+        });
+    }
+
+    /// Report file written when no --report-file is given, named after the start time
+    pub fn default_path(&self) -> PathBuf {
+        let stamp: String = format_rfc3339(self.start)
+            .chars()
+            .filter(|c| !matches!(c, '-' | ':'))
+            .collect();
+        PathBuf::from(format!(
+            "slashsum-report-{}.{}",
+            stamp,
+            self.format.extension()
+        ))
+    }
+
+    /// Writes the report; the run is considered finished now
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let end = SystemTime::now();
+        let text = match self.format {
+            ReportFormat::Dfxml => self.to_dfxml(end),
+            ReportFormat::Text => self.to_text(end),
+        };
+        fs::write(path, text)
+    }
+
+    /// Formats the report as Digital Forensics XML
+    fn to_dfxml(&self, end: SystemTime) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<dfxml xmloutputversion=\"1.0\" xmlns=\"{}\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+            DFXML_NAMESPACE
+        ));
+        xml.push_str("  <metadata>\n    <dc:type>Hash List</dc:type>\n  </metadata>\n");
+
+        xml.push_str("  <creator version=\"1.0\">\n");
+        xml.push_str("    <program>slashsum</program>\n");
+        xml.push_str(&element(4, "version", BUILD_VERSION));
+        xml.push_str("    <build_environment>\n");
+        xml.push_str(&element(6, "git_commit", GIT_COMMIT));
+        xml.push_str("    </build_environment>\n");
+        xml.push_str("    <execution_environment>\n");
+        xml.push_str(&element(6, "os_sysname", env::consts::OS));
+        xml.push_str(&element(6, "arch", env::consts::ARCH));
+        xml.push_str(&element(6, "host", &hostname()));
+        if let Some(uid) = uid() {
+            xml.push_str(&element(6, "uid", &uid.to_string()));
+        }
+        xml.push_str(&element(6, "username", &username()));
+        xml.push_str(&element(6, "start_time", &format_rfc3339(self.start)));
+        xml.push_str(&element(6, "end_time", &format_rfc3339(end)));
+        xml.push_str("    </execution_environment>\n");
+        xml.push_str(&element(4, "command_line", &self.command_line));
+        xml.push_str("  </creator>\n");
+
+        for entry in &self.entries {
+            let source = &entry.source;
+            xml.push_str("  <source>\n");
+            xml.push_str(&element(
+                4,
+                "image_filename",
+                &source.path.to_string_lossy(),
+            ));
+            xml.push_str(&element(4, "type", source.kind));
+            for (name, value) in [
+                ("device_vendor", &source.vendor),
+                ("device_model", &source.model),
+                ("device_sn", &source.serial),
+            ] {
+                if let Some(value) = value {
+                    xml.push_str(&element(4, name, value));
+                }
+            }
+            if let Some(size) = source.size {
+                xml.push_str(&element(4, "image_size", &size.to_string()));
+            }
+            xml.push_str("  </source>\n");
+        }
+
+        for entry in &self.entries {
+            let source = &entry.source;
+            xml.push_str("  <fileobject>\n");
+            xml.push_str(&element(4, "filename", &source.path.to_string_lossy()));
+            xml.push_str(&element(4, "filesize", &entry.checksums.size.to_string()));
+            if let Some(mtime) = source.mtime {
+                xml.push_str(&element(4, "mtime", &format_rfc3339(mtime)));
+            }
+            if entry.changed {
+                xml.push_str(&element(4, "error", "changed during hashing"));
+            }
+            xml.push_str(&hashdigests(4, &entry.checksums));
+
+            if let Some(acquisition) = &entry.acquisition {
+                xml.push_str("    <byte_runs>\n");
+                for segment in &acquisition.segments {
+                    xml.push_str(&format!(
+                        "      <byte_run file_offset=\"{}\" len=\"{}\">\n",
+                        segment.offset, segment.checksums.size
+                    ));
+                    xml.push_str(&hashdigests(8, &segment.checksums));
+                    xml.push_str("      </byte_run>\n");
+                }
+                xml.push_str("    </byte_runs>\n");
+                if !acquisition.bad.is_empty() {
+                    // Unreadable ranges, hashed and imaged as zeros
+                    xml.push_str("    <byte_runs facet=\"unreadable\">\n");
+                    for range in &acquisition.bad {
+                        xml.push_str(&format!(
+                            "      <byte_run file_offset=\"{}\" len=\"{}\" fill=\"0\"/>\n",
+                            range.offset, range.length
+                        ));
+                    }
+                    xml.push_str("    </byte_runs>\n");
+                }
+            }
+            xml.push_str("  </fileobject>\n");
+        }
+
+        xml.push_str("  <rusage>\n");
+        let seconds = end.duration_since(self.start).unwrap_or_default();
+        xml.push_str(&element(
+            4,
+            "clocktime",
+            &format!("{:.3}", seconds.as_secs_f64()),
+        ));
+        xml.push_str("  </rusage>\n");
+        xml.push_str("</dfxml>\n");
+        xml
+    }
+
+    /// Formats the report as plain text ending with sign-off lines
+    fn to_text(&self, end: SystemTime) -> String {
+        let mut text = String::from("SLASHSUM ACQUISITION REPORT\n\n");
+        let mut line =
+            |label: &str, value: &str| text.push_str(&format!("{:<11}{}\n", label, value));
+        line(
+            "Tool:",
+            &format!("slashsum {} (commit {})", BUILD_VERSION, GIT_COMMIT),
+        );
+        line("Command:", &self.command_line);
+        line(
+            "Host:",
+            &format!("{} ({} {})", hostname(), env::consts::OS, env::consts::ARCH),
+        );
+        line(
+            "User:",
+            &match uid() {
+                Some(uid) => format!("{} (uid {})", username(), uid),
+                None => username(),
+            },
+        );
+        line("Started:", &format_rfc3339(self.start));
+        line("Finished:", &format_rfc3339(end));
+
+        for entry in &self.entries {
+            let source = &entry.source;
+            text.push('\n');
+            let mut line =
+                |label: &str, value: &str| text.push_str(&format!("{:<11}{}\n", label, value));
+            line("Source:", &source.path.to_string_lossy());
+            line("Type:", source.kind);
+            for (label, value) in [
+                ("Vendor:", &source.vendor),
+                ("Model:", &source.model),
+                ("Serial:", &source.serial),
+            ] {
+                if let Some(value) = value {
+                    line(label, value);
+                }
+            }
+            if let Some(size) = source.size {
+                line("Size:", &format!("{} bytes", size));
+            }
+            if let Some(mtime) = source.mtime {
+                line("Modified:", &format_rfc3339(mtime));
+            }
+            line("Hashed:", &format!("{} bytes", entry.checksums.size));
+            for algo in Algorithm::ALL {
+                line(&format!("{}:", algo.name()), entry.checksums.get(algo));
+            }
+            if entry.changed {
+                line(
+                    "Status:",
+                    "CHANGED DURING HASHING (digests of an inconsistent snapshot)",
+                );
+            }
+
+            if let Some(acquisition) = &entry.acquisition {
+                for (index, segment) in acquisition.segments.iter().enumerate() {
+                    text.push_str(&format!(
+                        "Segment {}: offset {}, length {}\n",
+                        index + 1,
+                        segment.offset,
+                        segment.checksums.size
+                    ));
+                    for algo in Algorithm::ALL {
+                        text.push_str(&format!(
+                            "  {:<8}{}\n",
+                            format!("{}:", algo.name()),
+                            segment.checksums.get(algo)
+                        ));
+                    }
+                }
+                let zeroed: u64 = acquisition.bad.iter().map(|range| range.length).sum();
+                text.push_str(&format!(
+                    "Bad blocks: {} ({} bytes zero-filled)\n",
+                    acquisition.bad.len(),
+                    zeroed
+                ));
+                for range in &acquisition.bad {
+                    text.push_str(&format!(
+                        "  offset {}, length {}\n",
+                        range.offset, range.length
+                    ));
+                }
+            }
+        }
+
+        for role in ["Acquired by:", "Received by:"] {
+            text.push('\n');
+            for label in [role, "Signature:", "Date:"] {
+                text.push_str(&format!("{:<13}{}\n", label, "_".repeat(40)));
+            }
+        }
+        text
+    }
+}
+
+/// Formats a timestamp as RFC 3339 in UTC (e.g. "2026-10-18T07:30:12Z")
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153; // March = 0
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+/// Formats one XML element on its own line
+fn element(indent: usize, name: &str, value: &str) -> String {
+    format!(
+        "{:indent$}<{}>{}</{}>\n",
+        "",
+        name,
+        escape_xml(value),
+        name,
+        indent = indent
+    )
+}
+
+/// Formats the DFXML hashdigest elements of every algorithm
+fn hashdigests(indent: usize, checksums: &Checksums) -> String {
+    Algorithm::ALL
+        .into_iter()
+        .map(|algo| {
+            format!(
+                "{:indent$}<hashdigest type=\"{}\">{}</hashdigest>\n",
+                "",
+                algo.name().to_ascii_lowercase(),
+                checksums.get(algo),
+                indent = indent
+            )
+        })
+        .collect()
+}
+
+/// Escapes text for XML; control characters XML cannot carry become U+FFFD
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Name of the machine running the acquisition
+fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        // SAFETY: the buffer is valid for its whole length
+        if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } == 0 {
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..end]).into_owned();
+        }
+    }
+    env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Login name of the user running the acquisition
+fn username() -> String {
+    if let Some(name) = ["USER", "LOGNAME", "USERNAME"]
+        .into_iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    {
+        return name;
+    }
+    #[cfg(unix)]
+    {
+        // SAFETY: the passwd entry is only read before any other getpw* call
+        unsafe {
+            let entry = libc::getpwuid(libc::getuid());
+            if !entry.is_null() && !(*entry).pw_name.is_null() {
+                return std::ffi::CStr::from_ptr((*entry).pw_name)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
+    }
+    "unknown".to_string()
+}
+
+/// Numeric user id, where there is one
+fn uid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid has no preconditions and cannot fail
+        Some(unsafe { libc::getuid() })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Vendor, model and serial number of a block device, from sysfs
+/// Partitions report the identity of the disk holding them
+#[cfg(target_os = "linux")]
+fn device_identity(metadata: &fs::Metadata) -> (Option<String>, Option<String>, Option<String>) {
+    use std::os::unix::fs::MetadataExt;
+
+    if special_kind(metadata) != Some("block device") {
+        return (None, None, None);
+    }
+    let rdev = metadata.rdev();
+    let mut dir = PathBuf::from(format!(
+        "/sys/dev/block/{}:{}",
+        libc::major(rdev),
+        libc::minor(rdev)
+    ));
+    if dir.join("partition").exists()
+        && let Ok(partition) = fs::canonicalize(&dir)
+        && let Some(disk) = partition.parent()
+    {
+        dir = disk.to_path_buf();
+    }
+
+    // The first non-empty attribute wins: SCSI/ATA disks describe themselves
+    // under device/, NVMe and virtio disks have a serial at the top
+    let read = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            fs::read_to_string(dir.join(name))
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+    };
+    (
+        read(&["device/vendor"]),
+        read(&["device/model"]),
+        read(&["device/serial", "serial", "device/wwid"]),
+    )
+}
+
+/// Device identity is only read from Linux sysfs
+#[cfg(not(target_os = "linux"))]
+fn device_identity(_metadata: &fs::Metadata) -> (Option<String>, Option<String>, Option<String>) {
+    (None, None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forensic::{BadRange, Segment};
+    use crate::hasher::hash_reader;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(at(1_792_308_612)), "2026-10-18T07:30:12Z");
+        assert_eq!(format_rfc3339(at(4_107_542_399)), "2100-02-28T23:59:59Z");
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&apos;");
+        assert_eq!(escape_xml("x\u{1}y"), "x\u{FFFD}y");
+    }
+
+    fn sample_report(dir: &Path) -> Report {
+        let input = dir.join("evidence <1>.bin");
+        fs::write(&input, b"hello").unwrap();
+        let checksums = hash_reader(&b"hello"[..]).unwrap();
+        let acquisition = Acquisition {
+            checksums: checksums.clone(),
+            segments: vec![Segment {
+                offset: 0,
+                checksums: checksums.clone(),
+            }],
+            bad: vec![BadRange {
+                offset: 512,
+                length: 1024,
+            }],
+        };
+
+        let mut report = Report::new(
+            ReportFormat::Dfxml,
+            &["slashsum".to_string(), "--forensic".to_string()],
+        );
+        report.start = at(1_792_308_612);
+        report.add(
+            Source::identify(&input).unwrap(),
+            checksums,
+            false,
+            Some(acquisition),
+        );
+        report
+    }
+
+    #[test]
+    fn test_dfxml() {
+        let dir = tempdir().unwrap();
+        let report = sample_report(dir.path());
+        let xml = report.to_dfxml(at(1_792_308_672));
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<dfxml "));
+        assert!(xml.ends_with("</dfxml>\n"));
+        assert!(xml.contains("<start_time>2026-10-18T07:30:12Z</start_time>"));
+        assert!(xml.contains("<end_time>2026-10-18T07:31:12Z</end_time>"));
+        assert!(xml.contains("<command_line>slashsum --forensic</command_line>"));
+        assert!(xml.contains("evidence &lt;1&gt;.bin</filename>"));
+        assert!(xml.contains("<filesize>5</filesize>"));
+        assert!(xml.contains(
+            "<hashdigest type=\"sha256\">\
+             2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824</hashdigest>"
+        ));
+        assert!(xml.contains("<byte_run file_offset=\"0\" len=\"5\">"));
+        assert!(xml.contains("<byte_run file_offset=\"512\" len=\"1024\" fill=\"0\"/>"));
+        assert!(xml.contains("<clocktime>60.000</clocktime>"));
+
+        // Every opened element is closed
+        let opened = xml.matches("<fileobject>").count();
+        assert_eq!(opened, xml.matches("</fileobject>").count());
+        assert_eq!(opened, 1);
+    }
+
+    #[test]
+    fn test_text() {
+        let dir = tempdir().unwrap();
+        let mut report = sample_report(dir.path());
+        report.format = ReportFormat::Text;
+        let text = report.to_text(at(1_792_308_672));
+
+        assert!(text.contains("Started:   2026-10-18T07:30:12Z\n"));
+        assert!(text.contains("Finished:  2026-10-18T07:31:12Z\n"));
+        assert!(text.contains("Type:      regular file\n"));
+        assert!(text.contains("MD5:       5d41402abc4b2a76b9719d911017c592\n"));
+        assert!(text.contains("Segment 1: offset 0, length 5\n"));
+        assert!(
+            text.contains("Bad blocks: 1 (1024 bytes zero-filled)\n  offset 512, length 1024\n")
+        );
+        assert_eq!(text.matches("Signature:").count(), 2);
+
+        let path = dir.path().join("report.txt");
+        report.write(&path).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("Acquired by:"));
+        assert_eq!(
+            report.default_path(),
+            PathBuf::from("slashsum-report-20261018T073012Z.txt")
+        );
+        assert!(ReportFormat::from_name("pdf").is_err());
+    }
+}
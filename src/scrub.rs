@@ -0,0 +1,579 @@
+// Import standard library components
+use std::{
+    collections::{BTreeMap, BTreeSet}, // Recorded digests by relative path
+    error::Error,                      // Error trait for boxed errors
+    fs::{self, File, OpenOptions},     // File handling
+    io::{self, Read, Write},           // Reading files, logging events
+    path::{Path, PathBuf},             // Path manipulation
+    thread,                            // Sleeping when over the rate limit
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}, // Rate limiting and timestamps
+};
+
+// External crates
+use clap::{Args, ValueEnum}; // Command-line arguments
+use sha2::{Digest, Sha256}; // The only digest recorded
+
+use crate::atomic;
+use crate::cache::{escape, unescape};
+use crate::hasher::{CHUNK_SIZE, to_hex};
+use crate::interrupt::{self, Interrupted};
+use crate::{format_size, manifest::manifest_path, parse_path, walk};
+
+/// First line of the scrub state file, bumped when the layout changes
+const STATE_HEADER: &str = "slashsum-scrub 1";
+
+/// Name of the state file created in the scrubbed directory by default
+const DEFAULT_STATE: &str = ".slashsum-scrub";
+
+/// Progress is written to the state file at least this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// I/O scheduling priority requested from the kernel (Linux only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IoPriority {
+    Normal, // Leave the priority unchanged
+    Low,    // Lowest level of the best-effort class
+    Idle,   // Only use the disk when no other process does
+}
+
+/// Options of the `scrub` subcommand
+pub struct ScrubOptions {
+    pub root: PathBuf,
+    pub state: PathBuf,
+    pub log: Option<PathBuf>,
+    pub rate: Option<u64>, // Bytes per second
+    pub io_priority: IoPriority,
+}
+
+/// Arguments of `slashsum scrub`
+#[derive(Args, Debug)]
+pub struct ScrubArgs {
+    /// Directory to scrub
+    #[arg(value_name = "DIR", value_parser = parse_path)]
+    root: PathBuf,
+
+    /// Recorded digests (default: <DIR>/.slashsum-scrub)
+    #[arg(long, value_name = "FILE", value_parser = parse_path)]
+    state: Option<PathBuf>,
+
+    /// Append corruption events to FILE
+    #[arg(long, value_name = "FILE", value_parser = parse_path)]
+    log: Option<PathBuf>,
+
+    /// Limit the read throughput, in MB/s
+    #[arg(long, value_name = "MB/s", value_parser = parse_rate)]
+    rate: Option<u64>,
+
+    /// I/O priority (Linux)
+    #[arg(long, value_name = "PRIORITY", value_enum, default_value_t = IoPriority::Normal)]
+    io_priority: IoPriority,
+}
+
+impl ScrubArgs {
+    /// Places the state file in the scrubbed directory unless given
+    pub fn into_options(self) -> ScrubOptions {
+        ScrubOptions {
+            state: self.state.unwrap_or_else(|| self.root.join(DEFAULT_STATE)),
+            root: self.root,
+            log: self.log,
+            rate: self.rate,
+            io_priority: self.io_priority,
+        }
+    }
+}
+
+/// Recorded state of one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    size: u64,
+    mtime: i128, // Nanoseconds since the Unix epoch
+    sha256: String,
+}
+
+/// Outcome of one scrub run
+#[derive(Debug, Default)]
+pub struct ScrubReport {
+    pub verified: usize,
+    pub added: usize,
+    pub updated: usize, // Modified legitimately (size or mtime changed)
+    pub removed: usize, // Recorded but no longer present
+    pub skipped: usize, // Modified while being read
+    pub corrupted: Vec<String>,
+    pub unreadable: Vec<String>, // Could not be opened or read
+    pub bytes: u64,
+}
+
+/// Parses a rate in MB/s (1 MB = 1048576 bytes) into bytes per second
+fn parse_rate(value: &str) -> Result<u64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok((rate * 1_048_576.0).max(1.0) as u64),
+        _ => Err(format!("Invalid rate '{}' (expected MB/s)", value)),
+    }
+}
+
+/// Lowers the I/O priority of the whole process
+#[cfg(target_os = "linux")]
+fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    let value = match priority {
+        IoPriority::Normal => return Ok(()),
+        IoPriority::Low => (2 << IOPRIO_CLASS_SHIFT) | 7, // Best-effort, level 7
+        IoPriority::Idle => 3 << IOPRIO_CLASS_SHIFT,
+    };
+    // SAFETY: ioprio_set only reads its integer arguments
+    let result = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, value) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    match priority {
+        IoPriority::Normal => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "I/O priorities are only supported on Linux",
+        )),
+    }
+}
+
+/// Limits the average read throughput over the whole run
+struct Throttle {
+    rate: Option<u64>, // Bytes per second, None for unlimited
+    started: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    fn new(rate: Option<u64>) -> Throttle {
+        Throttle {
+            rate,
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Accounts for bytes read and sleeps until they are within the rate
+    fn consume(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+        if let Some(rate) = self.rate {
+            let due = Duration::from_secs_f64(self.bytes as f64 / rate as f64);
+            let elapsed = self.started.elapsed();
+            if due > elapsed {
+                thread::sleep(due - elapsed);
+            }
+        }
+    }
+}
+
+/// Reader feeding a throttle, so hashing never outpaces the rate limit
+struct ThrottledReader<'a, R> {
+    inner: R,
+    throttle: &'a mut Throttle,
+}
+
+impl<R: Read> Read for ThrottledReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.inner.read(buf)?;
+        self.throttle.consume(bytes);
+        Ok(bytes)
+    }
+}
+
+/// Modification time in nanoseconds since the Unix epoch
+fn mtime_nanos(metadata: &fs::Metadata) -> io::Result<i128> {
+    let mtime = metadata.modified()?;
+    Ok(match mtime.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_nanos() as i128,
+        Err(before) => -(before.duration().as_nanos() as i128),
+    })
+}
+
+/// Parses the state file; an unknown header means no recorded state
+fn parse_state(content: &str) -> BTreeMap<String, Record> {
+    let mut lines = content.lines();
+    if lines.next() != Some(STATE_HEADER) {
+        return BTreeMap::new();
+    }
+    lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            let [size, mtime, sha256, path] = fields[..] else {
+                return None;
+            };
+            let record = Record {
+                size: size.parse().ok()?,
+                mtime: mtime.parse().ok()?,
+                sha256: sha256.to_string(),
+            };
+            Some((unescape(path), record))
+        })
+        .collect()
+}
+
+/// Formats the state file: size, mtime, SHA256 and path of every file
+fn format_state(records: &BTreeMap<String, Record>) -> String {
+    let mut output = format!("{}\n", STATE_HEADER);
+    for (path, record) in records {
+        output.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            record.size,
+            record.mtime,
+            record.sha256,
+            escape(path)
+        ));
+    }
+    output
+}
+
+/// Writes the state file through a temporary file, so that an interruption
+/// or a crash while writing leaves the previous state intact
+fn save_state(path: &Path, records: &BTreeMap<String, Record>) -> io::Result<()> {
+    atomic::replace(path, format_state(records).as_bytes())
+}
+
+/// Appends an event (CORRUPTED, UNREADABLE) to the log file
+fn log_event(log: &Path, event: &str, path: &str, detail: &str) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+    writeln!(
+        file,
+        "{}\t{}\t{}\t{}",
+        timestamp,
+        event,
+        escape(path),
+        detail
+    )
+}
+
+/// Computes the SHA256 of a source, stopping at the next chunk on Ctrl-C
+fn sha256_reader<R: Read>(mut reader: R) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+    loop {
+        if interrupt::requested() {
+            return Err(Box::new(Interrupted { bytes: size }));
+        }
+        let bytes = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buffer[..bytes]);
+        size += bytes as u64;
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Reads a file and returns its current record, or None when it was
+/// modified while being read
+fn read_record(path: &Path, throttle: &mut Throttle) -> Result<Option<Record>, Box<dyn Error>> {
+    let before = fs::metadata(path)?;
+    let reader = ThrottledReader {
+        inner: File::open(path)?,
+        throttle,
+    };
+    let sha256 = sha256_reader(reader)?;
+
+    // A file written to while it was read cannot be judged
+    let after = fs::metadata(path)?;
+    let mtime = mtime_nanos(&before)?;
+    if after.len() != before.len() || mtime_nanos(&after)? != mtime {
+        return Ok(None);
+    }
+    Ok(Some(Record {
+        size: before.len(),
+        mtime,
+        sha256,
+    }))
+}
+
+/// Re-reads every file of the tree and compares it with the recorded state
+/// Files seen for the first time, or whose size or mtime changed, are
+/// (re)recorded; files with an unchanged size and mtime but a different
+/// digest are reported as corrupted and keep their recorded digest
+/// Progress is saved regularly, and when the run fails or is interrupted
+pub fn scrub(options: &ScrubOptions) -> Result<ScrubReport, Box<dyn Error>> {
+    // The state file stays locked for the whole run
+    let (_lock, content) = atomic::lock(&options.state)?;
+    let mut records = parse_state(&content);
+
+    let mut report = ScrubReport::default();
+    let mut seen = BTreeSet::new();
+    let result = scrub_files(options, &mut records, &mut report, &mut seen);
+    if result.is_ok() {
+        let total = records.len();
+        records.retain(|path, _| seen.contains(path));
+        report.removed = total - records.len();
+    }
+
+    save_state(&options.state, &records)?;
+    result?;
+    Ok(report)
+}
+
+/// Checks every file of the tree, updating `records` and `report`
+fn scrub_files(
+    options: &ScrubOptions,
+    records: &mut BTreeMap<String, Record>,
+    report: &mut ScrubReport,
+    seen: &mut BTreeSet<String>,
+) -> Result<(), Box<dyn Error>> {
+    let excluded: Vec<PathBuf> = [
+        Some(options.state.clone()),
+        Some(atomic::temporary_path(&options.state)),
+        options.log.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| path.canonicalize().ok())
+    .collect();
+
+    let mut throttle = Throttle::new(options.rate);
+    let mut last_save = Instant::now();
+
+    // Files recorded below a directory that cannot be listed keep their digests
+    let tree = walk::collect_tree(&options.root)?;
+    for dir in &tree.unreadable {
+        let relative = format!(
+            "{}/",
+            manifest_path(dir.strip_prefix(&options.root)?, None, None)?
+        );
+        println!("UNREADABLE: {}", relative);
+        if let Some(log) = &options.log {
+            log_event(log, "UNREADABLE", &relative, "cannot list directory")?;
+        }
+        seen.extend(
+            records
+                .keys()
+                .filter(|path| path.starts_with(&relative))
+                .cloned(),
+        );
+        report.unreadable.push(relative);
+    }
+
+    for file in tree.files {
+        if file
+            .canonicalize()
+            .is_ok_and(|path| excluded.contains(&path))
+        {
+            continue;
+        }
+        let relative = manifest_path(file.strip_prefix(&options.root)?, None, None)?;
+        if last_save.elapsed() >= SAVE_INTERVAL {
+            save_state(&options.state, records)?;
+            last_save = Instant::now();
+        }
+
+        let result = read_record(&file, &mut throttle);
+        report.bytes = throttle.bytes;
+        let record = match result {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                println!("Skipped (modified while reading): {}", relative);
+                report.skipped += 1;
+                seen.insert(relative);
+                continue;
+            }
+            Err(e) if e.is::<Interrupted>() => return Err(e),
+            Err(e) => {
+                // The recorded digest is kept, the file is checked again next time
+                println!("UNREADABLE: {} ({})", relative, e);
+                if let Some(log) = &options.log {
+                    log_event(log, "UNREADABLE", &relative, &e.to_string())?;
+                }
+                report.unreadable.push(relative.clone());
+                seen.insert(relative);
+                continue;
+            }
+        };
+
+        match records.get(&relative) {
+            None => {
+                report.added += 1;
+                records.insert(relative.clone(), record);
+            }
+            Some(known) if known.size != record.size || known.mtime != record.mtime => {
+                report.updated += 1;
+                records.insert(relative.clone(), record);
+            }
+            Some(known) if known.sha256 == record.sha256 => report.verified += 1,
+            Some(known) => {
+                println!(
+                    "CORRUPTED: {} (expected SHA256 {}, got {})",
+                    relative, known.sha256, record.sha256
+                );
+                if let Some(log) = &options.log {
+                    let detail = format!("expected SHA256 {}\tgot {}", known.sha256, record.sha256);
+                    log_event(log, "CORRUPTED", &relative, &detail)?;
+                }
+                report.corrupted.push(relative.clone());
+            }
+        }
+        seen.insert(relative);
+    }
+    Ok(())
+}
+
+/// Runs the `scrub` subcommand
+/// Returns false when corrupted or unreadable files were found
+pub fn run(options: &ScrubOptions) -> Result<bool, Box<dyn Error>> {
+    if !options.root.is_dir() {
+        return Err(format!("'{}' is not a directory", options.root.display()).into());
+    }
+    if let Err(e) = set_io_priority(options.io_priority) {
+        eprintln!("Warning: cannot set I/O priority: {}", e);
+    }
+
+    // Stop at the next chunk on Ctrl-C or SIGTERM, keeping the progress made
+    interrupt::install()?;
+
+    let start_time = Instant::now();
+    let report = scrub(options).inspect_err(|_| {
+        if interrupt::requested() {
+            eprintln!(
+                "Interrupted, progress saved to '{}'",
+                options.state.display()
+            );
+        }
+    })?;
+
+    println!(
+        "Verified: {}, added: {}, updated: {}, removed: {}, skipped: {}, corrupted: {}, unreadable: {}",
+        report.verified,
+        report.added,
+        report.updated,
+        report.removed,
+        report.skipped,
+        report.corrupted.len(),
+        report.unreadable.len()
+    );
+    println!("Read:  {}", format_size(report.bytes));
+    println!("Time:  {:.2?}", start_time.elapsed());
+    Ok(report.corrupted.is_empty() && report.unreadable.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_args(args: &[String]) -> Result<ScrubOptions, String> {
+        Ok(crate::cli::try_parse::<ScrubArgs>(args)?.into_options())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options =
+            parse_args(&args(&["data", "--rate", "0.5", "--io-priority", "idle"])).unwrap();
+        assert_eq!(options.state, PathBuf::from("data").join(DEFAULT_STATE));
+        assert_eq!(options.rate, Some(524_288));
+        assert_eq!(options.io_priority, IoPriority::Idle);
+
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["a", "b"])).is_err());
+        assert!(parse_args(&args(&["a", "--rate", "0"])).is_err());
+        assert!(parse_args(&args(&["a", "--io-priority", "high"])).is_err());
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::new(Some(1_000_000));
+        let start = Instant::now();
+        throttle.consume(100_000);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn test_scrub() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("data");
+        fs::create_dir(&root).unwrap();
+        let file = root.join("file.bin");
+        let gone = root.join("gone.bin");
+        fs::write(&file, b"original").unwrap();
+        fs::write(&gone, b"temporary").unwrap();
+
+        let options = ScrubOptions {
+            state: root.join(DEFAULT_STATE),
+            root: root.clone(),
+            log: Some(dir.path().join("scrub.log")),
+            rate: None,
+            io_priority: IoPriority::Normal,
+        };
+
+        // First run records every file
+        let report = scrub(&options).unwrap();
+        assert_eq!((report.added, report.verified), (2, 0));
+
+        // Second run verifies them; removed files are dropped
+        fs::remove_file(&gone).unwrap();
+        let report = scrub(&options).unwrap();
+        assert_eq!((report.added, report.verified, report.removed), (0, 1, 1));
+
+        // Same size and mtime, different content: silent corruption
+        let mtime = fs::metadata(&file).unwrap().modified().unwrap();
+        fs::write(&file, b"0riginal").unwrap();
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let report = scrub(&options).unwrap();
+        assert_eq!(report.corrupted, vec!["file.bin"]);
+        let log = fs::read_to_string(options.log.as_ref().unwrap()).unwrap();
+        assert!(log.contains("CORRUPTED\tfile.bin"));
+
+        // A newer mtime is a legitimate modification
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime + Duration::from_secs(10))
+            .unwrap();
+        let report = scrub(&options).unwrap();
+        assert_eq!((report.updated, report.corrupted.len()), (1, 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_scrub_unreadable() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("file.bin"), b"data").unwrap();
+        // Reading the start of /proc/self/mem fails with EIO, like a bad sector
+        std::os::unix::fs::symlink("/proc/self/mem", dir.path().join("bad.bin")).unwrap();
+
+        let options = ScrubOptions {
+            state: dir.path().join(DEFAULT_STATE),
+            root: dir.path().to_path_buf(),
+            log: None,
+            rate: None,
+            io_priority: IoPriority::Normal,
+        };
+        let report = scrub(&options).unwrap();
+        assert_eq!(report.unreadable, vec!["bad.bin"]);
+        assert_eq!(report.added, 1);
+
+        // The state is saved through a temporary file that does not remain
+        let state = fs::read_to_string(&options.state).unwrap();
+        assert_eq!(
+            parse_state(&state).keys().collect::<Vec<_>>(),
+            vec!["file.bin"]
+        );
+        assert!(!atomic::temporary_path(&options.state).exists());
+    }
+}
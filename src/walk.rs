@@ -0,0 +1,144 @@
+// Import standard library components
+use std::{
+    fs,                    // File system access
+    io,                    // I/O errors
+    path::{Path, PathBuf}, // Path manipulation
+};
+
+/// Files found below a path, and the directories that could not be read
+#[derive(Debug, Default)]
+pub struct Tree {
+    pub files: Vec<PathBuf>,
+    pub unreadable: Vec<PathBuf>,
+}
+
+/// Collects every regular file below a path, sorted for deterministic output
+/// A plain file is returned as-is; symbolic links to directories are not followed
+/// to avoid loops
+pub fn collect_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(collect_tree(path)?.files)
+}
+
+/// Same as `collect_files`, also listing the subdirectories that were skipped
+/// An unreadable subdirectory is reported with a warning and skipped; only
+/// the path itself must be readable
+pub fn collect_tree(path: &Path) -> io::Result<Tree> {
+    let mut tree = Tree::default();
+    if fs::metadata(path)?.is_dir() {
+        for entry in fs::read_dir(path)? {
+            walk_entry(path, entry, &mut tree);
+        }
+        tree.files.sort();
+    } else {
+        tree.files.push(path.to_path_buf());
+    }
+    Ok(tree)
+}
+
+/// Identifies the underlying file (device and inode) so hard links can be recognized
+/// Returns None on platforms without inode numbers
+pub fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Appends a directory entry if it is a regular file, or the regular files
+/// below it if it is a directory
+fn walk_entry(dir: &Path, entry: io::Result<fs::DirEntry>, tree: &mut Tree) {
+    let path = match entry {
+        Ok(entry) => entry.path(),
+        Err(e) => {
+            eprintln!("Warning: {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    let file_type = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata.file_type(),
+        Err(e) => {
+            eprintln!("Warning: {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    if file_type.is_dir() {
+        match fs::read_dir(&path) {
+            Ok(entries) => {
+                for entry in entries {
+                    walk_entry(&path, entry, tree);
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: {}: {}, skipped", path.display(), e);
+                tree.unreadable.push(path);
+            }
+        }
+    } else if file_type.is_file() {
+        tree.files.push(path);
+    } else if file_type.is_symlink() && path.is_file() {
+        // Follow links to files, but never links to directories
+        tree.files.push(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_collect_files_sorted_and_recursive() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("b.txt"), b"b").unwrap();
+        fs::write(dir.path().join("a.txt"), b"a").unwrap();
+        fs::write(dir.path().join("sub").join("c.txt"), b"c").unwrap();
+
+        let files = collect_files(dir.path()).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("a.txt"),
+                dir.path().join("b.txt"),
+                dir.path().join("sub").join("c.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_files_single_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("single.bin");
+        fs::write(&file, b"data").unwrap();
+
+        assert_eq!(collect_files(&file).unwrap(), vec![file]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_tree_skips_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("hidden.txt"), b"x").unwrap();
+        fs::write(dir.path().join("a.txt"), b"a").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            return; // Permissions do not apply (running as root)
+        }
+
+        let tree = collect_tree(dir.path()).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(tree.files, vec![dir.path().join("a.txt")]);
+        assert_eq!(tree.unreadable, vec![locked]);
+    }
+}
@@ -0,0 +1,408 @@
+// Import standard library components
+use std::{
+    error::Error,                   // Error trait for boxed errors
+    io::{self, Write},              // Event output
+    path::PathBuf,                  // Path manipulation
+    time::{SystemTime, UNIX_EPOCH}, // Event timestamps
+};
+
+// External crates
+use clap::Args; // Command-line arguments
+
+use crate::hasher::{Checksums, hash_file};
+use crate::parse_path;
+
+/// Options of the `watch` subcommand
+#[derive(Args, Debug)]
+pub struct WatchOptions {
+    /// Files or directories to watch
+    #[arg(value_name = "PATH", required = true, value_parser = parse_path)]
+    pub paths: Vec<PathBuf>,
+    /// Hash existing files first
+    #[arg(long)]
+    pub initial: bool,
+}
+
+/// File system change relevant to checksums
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    Written(PathBuf), // Closed after writing, or moved into a watched directory
+    Removed(PathBuf), // Deleted, or moved out of a watched directory
+    Overflow,         // The kernel dropped events
+}
+
+/// Seconds since the Unix epoch
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Builds the NDJSON event for a freshly hashed file
+fn hashed_event(path: &str, checksums: &Checksums, timestamp: u64) -> serde_json::Value {
+    serde_json::json!({
+        "event": "hashed",
+        "path": path,
+        "size": checksums.size,
+        "crc32": checksums.crc32,
+        "md5": checksums.md5,
+        "sha1": checksums.sha1,
+        "sha256": checksums.sha256,
+        "sha512": checksums.sha512,
+        "timestamp": timestamp,
+    })
+}
+
+/// Builds the NDJSON event for one change
+/// Files that cannot be read (e.g. deleted right after being closed) give an error event
+fn change_event(change: &Change) -> serde_json::Value {
+    let now = timestamp();
+    match change {
+        Change::Written(path) => {
+            let name = path.to_string_lossy();
+            match hash_file(path) {
+                Ok(checksums) => hashed_event(&name, &checksums, now),
+                Err(e) => serde_json::json!({
+                    "event": "error",
+                    "path": name,
+                    "message": e.to_string(),
+                    "timestamp": now,
+                }),
+            }
+        }
+        Change::Removed(path) => serde_json::json!({
+            "event": "removed",
+            "path": path.to_string_lossy(),
+            "timestamp": now,
+        }),
+        Change::Overflow => serde_json::json!({ "event": "overflow", "timestamp": now }),
+    }
+}
+
+/// Writes one event per line, flushed so consumers see it immediately
+fn emit(event: &serde_json::Value) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", event)?;
+    stdout.flush()
+}
+
+/// Runs the `watch` subcommand until interrupted
+pub fn run(options: &WatchOptions) -> Result<(), Box<dyn Error>> {
+    for path in &options.paths {
+        if !path.exists() {
+            return Err(format!("'{}' not found", path.display()).into());
+        }
+    }
+
+    let (mut watcher, existing) = inotify_watcher::Watcher::new(&options.paths)?;
+    if options.initial {
+        for file in existing {
+            emit(&change_event(&Change::Written(file)))?;
+        }
+    }
+
+    loop {
+        for change in watcher.next_changes()? {
+            emit(&change_event(&change))?;
+        }
+    }
+}
+
+/// Recursive watcher built on inotify (Linux only)
+#[cfg(target_os = "linux")]
+mod inotify_watcher {
+    use super::Change;
+    use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+    use std::{collections::HashMap, ffi::OsString, fs, io, path::Path, path::PathBuf};
+
+    /// Events that can change the checksums of a file
+    fn mask() -> WatchMask {
+        WatchMask::CLOSE_WRITE
+            | WatchMask::MOVED_TO
+            | WatchMask::MOVED_FROM
+            | WatchMask::CREATE
+            | WatchMask::DELETE
+    }
+
+    /// Directory watched by inotify
+    struct Watched {
+        path: PathBuf,
+        // Files of interest by name, with the path they were given as;
+        // None when the whole directory is watched
+        files: Option<HashMap<OsString, PathBuf>>,
+    }
+
+    pub struct Watcher {
+        inotify: Inotify,
+        watched: HashMap<WatchDescriptor, Watched>,
+        buffer: Vec<u8>,
+    }
+
+    impl Watcher {
+        /// Watches files and directory trees; also returns the files already present
+        pub fn new(paths: &[PathBuf]) -> io::Result<(Watcher, Vec<PathBuf>)> {
+            let mut watcher = Watcher {
+                inotify: Inotify::init()?,
+                watched: HashMap::new(),
+                buffer: vec![0; 64 * 1024],
+            };
+            let mut existing = Vec::new();
+            for path in paths {
+                if path.is_dir() {
+                    watcher.add_tree(path, &mut existing)?;
+                } else {
+                    watcher.add_file(path)?;
+                    existing.push(path.clone());
+                }
+            }
+            Ok((watcher, existing))
+        }
+
+        /// Watches a single file through its directory: editors and `mv` replace
+        /// a file by renaming another one over it, which a watch on the file's
+        /// own inode would never see
+        fn add_file(&mut self, path: &Path) -> io::Result<()> {
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' is not a file", path.display()),
+                ));
+            };
+            let dir = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            let wd = self.inotify.watches().add(
+                dir,
+                WatchMask::CLOSE_WRITE
+                    | WatchMask::MOVED_TO
+                    | WatchMask::CREATE
+                    | WatchMask::ONLYDIR
+                    | WatchMask::MASK_ADD,
+            )?;
+            let watched = self.watched.entry(wd).or_insert_with(|| Watched {
+                path: dir.to_path_buf(),
+                files: Some(HashMap::new()),
+            });
+            if let Some(files) = &mut watched.files {
+                files.insert(name.to_os_string(), path.to_path_buf());
+            }
+            Ok(())
+        }
+
+        /// Watches a directory and its subdirectories, collecting their files
+        /// (inotify is not recursive, so each directory needs its own watch)
+        fn add_tree(&mut self, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+            let wd = self.inotify.watches().add(
+                dir,
+                mask() | WatchMask::ONLYDIR | WatchMask::DONT_FOLLOW | WatchMask::MASK_ADD,
+            )?;
+            self.watched.insert(
+                wd,
+                Watched {
+                    path: dir.to_path_buf(),
+                    files: None,
+                },
+            );
+
+            let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect();
+            entries.sort();
+            for path in entries {
+                let Ok(metadata) = fs::symlink_metadata(&path) else {
+                    continue; // Deleted meanwhile
+                };
+                if metadata.is_dir() {
+                    self.add_tree(&path, files)?;
+                } else if metadata.is_file() {
+                    files.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        /// Blocks until changes are available
+        pub fn next_changes(&mut self) -> io::Result<Vec<Change>> {
+            let events: Vec<_> = self
+                .inotify
+                .read_events_blocking(&mut self.buffer)?
+                .map(|event| event.to_owned())
+                .collect();
+
+            let mut changes = Vec::new();
+            for event in events {
+                if event.mask.contains(EventMask::Q_OVERFLOW) {
+                    changes.push(Change::Overflow);
+                    continue;
+                }
+                if event.mask.contains(EventMask::IGNORED) {
+                    self.watched.remove(&event.wd); // Watched path was removed
+                    continue;
+                }
+                let Some(watched) = self.watched.get(&event.wd) else {
+                    continue;
+                };
+                let path = match (&watched.files, &event.name) {
+                    (None, Some(name)) => watched.path.join(name),
+                    (None, None) => watched.path.clone(),
+                    // Only the watched files of the directory matter
+                    (Some(files), Some(name)) => match files.get(name) {
+                        Some(path) if !event.mask.contains(EventMask::ISDIR) => path.clone(),
+                        _ => continue,
+                    },
+                    (Some(_), None) => continue,
+                };
+
+                if event.mask.contains(EventMask::ISDIR) {
+                    // Files of a new directory may be written before it is watched
+                    if event
+                        .mask
+                        .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+                    {
+                        let mut files = Vec::new();
+                        if self.add_tree(&path, &mut files).is_ok() {
+                            changes.extend(files.into_iter().map(Change::Written));
+                        }
+                    }
+                } else if event
+                    .mask
+                    .intersects(EventMask::CLOSE_WRITE | EventMask::MOVED_TO)
+                {
+                    changes.push(Change::Written(path));
+                } else if event
+                    .mask
+                    .intersects(EventMask::DELETE | EventMask::MOVED_FROM)
+                {
+                    changes.push(Change::Removed(path));
+                }
+            }
+            Ok(changes)
+        }
+    }
+}
+
+/// Placeholder for platforms without inotify
+#[cfg(not(target_os = "linux"))]
+mod inotify_watcher {
+    use super::Change;
+    use std::{io, path::PathBuf};
+
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new(_paths: &[PathBuf]) -> io::Result<(Watcher, Vec<PathBuf>)> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "watch is only supported on Linux (inotify)",
+            ))
+        }
+
+        pub fn next_changes(&mut self) -> io::Result<Vec<Change>> {
+            unreachable!("the watcher cannot be created")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_args(args: &[String]) -> Result<WatchOptions, String> {
+        crate::cli::try_parse(args)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&["out", "--initial"])).unwrap();
+        assert_eq!(options.paths, vec![PathBuf::from("out")]);
+        assert!(options.initial);
+
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["out", "--json"])).is_err());
+    }
+
+    #[test]
+    fn test_change_event() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("abc.txt");
+        fs::write(&file, b"abc").unwrap();
+
+        let event = change_event(&Change::Written(file.clone()));
+        assert_eq!(event["event"], "hashed");
+        assert_eq!(event["size"], 3);
+        assert_eq!(event["md5"], "900150983cd24fb0d6963f7d28e17f72");
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(
+            change_event(&Change::Written(file.clone()))["event"],
+            "error"
+        );
+        assert_eq!(change_event(&Change::Removed(file))["event"], "removed");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_watcher() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("existing.txt"), b"old").unwrap();
+
+        let (mut watcher, existing) =
+            inotify_watcher::Watcher::new(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(existing, vec![dir.path().join("existing.txt")]);
+
+        // Files of a new subdirectory are picked up as well
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        let mut changes = watcher.next_changes().unwrap();
+        fs::write(sub.join("new.txt"), b"new").unwrap();
+        while !changes.contains(&Change::Written(sub.join("new.txt"))) {
+            changes.extend(watcher.next_changes().unwrap());
+        }
+
+        fs::remove_file(dir.path().join("existing.txt")).unwrap();
+        let changes = watcher.next_changes().unwrap();
+        assert_eq!(
+            changes,
+            vec![Change::Removed(dir.path().join("existing.txt"))]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_watcher_single_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("config.txt");
+        fs::write(&file, b"v1").unwrap();
+        fs::write(dir.path().join("other.txt"), b"other").unwrap();
+
+        let (mut watcher, existing) =
+            inotify_watcher::Watcher::new(std::slice::from_ref(&file)).unwrap();
+        assert_eq!(existing, vec![file.clone()]);
+
+        // Replaced by rename, as editors save: seen, and seen again afterwards
+        for content in [&b"v2"[..], b"v3"] {
+            let temporary = dir.path().join("config.txt.tmp");
+            fs::write(&temporary, content).unwrap();
+            fs::rename(&temporary, &file).unwrap();
+            let mut changes = Vec::new();
+            while !changes.contains(&Change::Written(file.clone())) {
+                changes.extend(watcher.next_changes().unwrap());
+            }
+            // Other files of the directory are ignored
+            assert!(
+                changes
+                    .iter()
+                    .all(|change| *change == Change::Written(file.clone()))
+            );
+        }
+    }
+}
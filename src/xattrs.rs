@@ -0,0 +1,195 @@
+// Import standard library components
+use std::{
+    io,                             // I/O errors
+    path::Path,                     // Path manipulation
+    time::{SystemTime, UNIX_EPOCH}, // Modification times
+};
+
+use crate::hasher::{Algorithm, Checksums};
+
+/// Namespace of the attributes: user.checksum.<algo> and user.checksum.mtime
+const PREFIX: &str = "user.checksum.";
+
+/// Checksums read back from the attributes of a file
+#[derive(Debug, Default)]
+pub struct Stored {
+    pub digests: Vec<(Algorithm, String)>,
+    pub mtime: Option<String>, // "<seconds>.<nanoseconds>" when the digests were stored
+}
+
+/// Result of comparing a file with the checksums stored in its attributes
+#[derive(Debug, PartialEq, Eq)]
+pub enum XattrStatus {
+    Ok,                        // Same content
+    TimestampChanged,          // Same content, but the file was touched
+    Stale,                     // Modified since the checksums were stored
+    Corrupted(Vec<Algorithm>), // Same timestamp, different content
+    Missing,                   // No stored checksums
+}
+
+impl XattrStatus {
+    /// Line printed after the checksums
+    pub fn describe(&self) -> String {
+        match self {
+            XattrStatus::Ok => "OK".to_string(),
+            XattrStatus::TimestampChanged => {
+                "OK (timestamp changed, content identical)".to_string()
+            }
+            XattrStatus::Stale => "STALE (file modified since checksums were stored)".to_string(),
+            XattrStatus::Corrupted(algorithms) => {
+                let names: Vec<&str> = algorithms.iter().map(|algo| algo.name()).collect();
+                format!("CORRUPTED (same timestamp, {} differ)", names.join(", "))
+            }
+            XattrStatus::Missing => "MISSING (no checksums stored)".to_string(),
+        }
+    }
+}
+
+/// Formats a modification time as "<seconds>.<nanoseconds>" since the Unix epoch
+pub fn format_mtime(mtime: SystemTime) -> String {
+    let since_epoch = mtime.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!(
+        "{}.{:09}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Compares stored attributes with freshly computed checksums
+/// A file whose timestamp changed is stale, unless its content is identical;
+/// a file with the same timestamp but a different content is corrupted
+pub fn classify(stored: &Stored, checksums: &Checksums, current_mtime: &str) -> XattrStatus {
+    if stored.digests.is_empty() {
+        return XattrStatus::Missing;
+    }
+
+    let differing: Vec<Algorithm> = stored
+        .digests
+        .iter()
+        .filter(|(algo, digest)| !checksums.get(*algo).eq_ignore_ascii_case(digest))
+        .map(|(algo, _)| *algo)
+        .collect();
+    let same_mtime = stored.mtime.as_deref() == Some(current_mtime);
+
+    match (differing.is_empty(), same_mtime) {
+        (true, true) => XattrStatus::Ok,
+        (true, false) => XattrStatus::TimestampChanged,
+        (false, false) => XattrStatus::Stale,
+        (false, true) => XattrStatus::Corrupted(differing),
+    }
+}
+
+/// Name of the attribute holding one digest (e.g. "user.checksum.sha256")
+fn attribute(algo: Algorithm) -> String {
+    format!("{}{}", PREFIX, algo.name().to_ascii_lowercase())
+}
+
+/// Stores every digest and the modification time they correspond to
+#[cfg(unix)]
+pub fn store(path: &Path, checksums: &Checksums, mtime: SystemTime) -> io::Result<()> {
+    for algo in Algorithm::ALL {
+        xattr::set(path, attribute(algo), checksums.get(algo).as_bytes())?;
+    }
+    xattr::set(
+        path,
+        format!("{}mtime", PREFIX),
+        format_mtime(mtime).as_bytes(),
+    )
+}
+
+/// Reads the stored digests and modification time
+#[cfg(unix)]
+pub fn read(path: &Path) -> io::Result<Stored> {
+    let text = |name: String| -> io::Result<Option<String>> {
+        Ok(xattr::get(path, name)?.map(|value| String::from_utf8_lossy(&value).into_owned()))
+    };
+
+    let mut digests = Vec::new();
+    for algo in Algorithm::ALL {
+        if let Some(digest) = text(attribute(algo))? {
+            digests.push((algo, digest));
+        }
+    }
+    Ok(Stored {
+        digests,
+        mtime: text(format!("{}mtime", PREFIX))?,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn store(_path: &Path, _checksums: &Checksums, _mtime: SystemTime) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "extended attributes are not supported on this platform",
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn read(_path: &Path) -> io::Result<Stored> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "extended attributes are not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::hash_reader;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_mtime() {
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 42);
+        assert_eq!(format_mtime(mtime), "1700000000.000000042");
+    }
+
+    #[test]
+    fn test_classify() {
+        let checksums = hash_reader(&b"abc"[..]).unwrap();
+        let stored = |digest: &str| Stored {
+            digests: vec![(Algorithm::Md5, digest.to_string())],
+            mtime: Some("1.0".to_string()),
+        };
+        let good = stored(&checksums.md5);
+        let bad = stored(&"0".repeat(32));
+
+        assert_eq!(classify(&good, &checksums, "1.0"), XattrStatus::Ok);
+        assert_eq!(
+            classify(&good, &checksums, "2.0"),
+            XattrStatus::TimestampChanged
+        );
+        assert_eq!(classify(&bad, &checksums, "2.0"), XattrStatus::Stale);
+        assert_eq!(
+            classify(&bad, &checksums, "1.0"),
+            XattrStatus::Corrupted(vec![Algorithm::Md5])
+        );
+        assert_eq!(
+            classify(&Stored::default(), &checksums, "1.0"),
+            XattrStatus::Missing
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_store_and_read() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let checksums = hash_reader(&b""[..]).unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(5);
+
+        match store(file.path(), &checksums, mtime) {
+            Ok(()) => {}
+            // Some file systems (e.g. older tmpfs) reject user attributes
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return,
+            Err(e) => panic!("{}", e),
+        }
+
+        let stored = read(file.path()).unwrap();
+        assert_eq!(stored.digests.len(), Algorithm::ALL.len());
+        assert_eq!(stored.mtime.as_deref(), Some("5.000000000"));
+        assert_eq!(
+            classify(&stored, &checksums, "5.000000000"),
+            XattrStatus::Ok
+        );
+    }
+}